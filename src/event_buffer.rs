@@ -1,8 +1,21 @@
-use std::io::{Read, Write, Result};
+use std::io::{self, Read, Write, Result};
 use std::ptr;
 use std::fmt;
 use std::cmp;
-use psocket::{self, TcpSocket};
+use psocket::{self, TcpSocket, SocketAddr};
+use EventFlags;
+#[cfg(unix)]
+use std::io::ErrorKind;
+#[cfg(unix)]
+use std::net::Shutdown;
+#[cfg(unix)]
+use std::fs::File;
+#[cfg(unix)]
+use std::os::unix::io::AsRawFd;
+#[cfg(unix)]
+use libc;
+#[cfg(unix)]
+use std::os::unix::net::{UnixStream, UnixListener};
 
 pub struct Buffer {
     val: Vec<u8>,
@@ -121,33 +134,257 @@ impl Write for Buffer {
     }
 }
 
+/// EventBuffer中的流式socket, 可以是TCP或者Unix域socket
+/// Fd为管道, 终端等普通的文件句柄, 以read/write读写
+#[derive(Debug)]
+pub enum StreamSocket {
+    Tcp(TcpSocket),
+    #[cfg(unix)]
+    Unix(UnixStream),
+    #[cfg(unix)]
+    UnixListener(UnixListener),
+    #[cfg(unix)]
+    Fd(File),
+}
+
+impl StreamSocket {
+    pub fn as_raw_socket(&self) -> psocket::SOCKET {
+        match *self {
+            StreamSocket::Tcp(ref socket) => socket.as_raw_socket(),
+            #[cfg(unix)]
+            StreamSocket::Unix(ref socket) => socket.as_raw_fd(),
+            #[cfg(unix)]
+            StreamSocket::UnixListener(ref socket) => socket.as_raw_fd(),
+            #[cfg(unix)]
+            StreamSocket::Fd(ref file) => file.as_raw_fd(),
+        }
+    }
+
+    pub fn as_tcp(&self) -> Option<&TcpSocket> {
+        match *self {
+            StreamSocket::Tcp(ref socket) => Some(socket),
+            #[cfg(unix)]
+            _ => None,
+        }
+    }
+
+    pub fn as_tcp_mut(&mut self) -> Option<&mut TcpSocket> {
+        match *self {
+            StreamSocket::Tcp(ref mut socket) => Some(socket),
+            #[cfg(unix)]
+            _ => None,
+        }
+    }
+
+    /// 接受新的TCP连接, 仅对TCP的监听socket有效
+    pub fn accept(&self) -> Result<(TcpSocket, SocketAddr)> {
+        match *self {
+            StreamSocket::Tcp(ref socket) => socket.accept(),
+            #[cfg(unix)]
+            _ => Err(io::Error::new(ErrorKind::InvalidInput, "not a tcp listener")),
+        }
+    }
+
+    /// 关闭socket, Unix域socket在此关闭读写, 句柄随EventBuffer释放时关闭
+    pub fn close(&self) {
+        match *self {
+            StreamSocket::Tcp(ref socket) => socket.close(),
+            #[cfg(unix)]
+            StreamSocket::Unix(ref socket) => {
+                let _ = socket.shutdown(Shutdown::Both);
+            }
+            #[cfg(unix)]
+            StreamSocket::UnixListener(_) | StreamSocket::Fd(_) => (),
+        }
+    }
+
+    /// 关闭写端, 向对端发送FIN, 读端不受影响
+    pub fn shutdown_write(&self) -> Result<()> {
+        match *self {
+            StreamSocket::Tcp(ref socket) => socket.shutdown(psocket::Shutdown::Write),
+            #[cfg(unix)]
+            StreamSocket::Unix(ref socket) => socket.shutdown(Shutdown::Write),
+            #[cfg(unix)]
+            StreamSocket::UnixListener(_) | StreamSocket::Fd(_) => {
+                Err(io::Error::new(ErrorKind::InvalidInput, "not a connected stream socket"))
+            }
+        }
+    }
+
+    pub fn take_error(&self) -> Result<Option<io::Error>> {
+        match *self {
+            StreamSocket::Tcp(ref socket) => socket.take_error(),
+            #[cfg(unix)]
+            StreamSocket::Unix(ref socket) => socket.take_error(),
+            #[cfg(unix)]
+            StreamSocket::UnixListener(ref socket) => socket.take_error(),
+            #[cfg(unix)]
+            StreamSocket::Fd(_) => Ok(None),
+        }
+    }
+
+    pub fn set_nonblocking(&self, nonblocking: bool) -> Result<()> {
+        match *self {
+            StreamSocket::Tcp(ref socket) => socket.set_nonblocking(nonblocking),
+            #[cfg(unix)]
+            StreamSocket::Unix(ref socket) => socket.set_nonblocking(nonblocking),
+            #[cfg(unix)]
+            StreamSocket::UnixListener(ref socket) => socket.set_nonblocking(nonblocking),
+            #[cfg(unix)]
+            StreamSocket::Fd(ref file) => set_fd_nonblocking(file.as_raw_fd(), nonblocking),
+        }
+    }
+}
+
+impl Read for StreamSocket {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        match *self {
+            StreamSocket::Tcp(ref mut socket) => socket.read(buf),
+            #[cfg(unix)]
+            StreamSocket::Unix(ref mut socket) => socket.read(buf),
+            #[cfg(unix)]
+            StreamSocket::Fd(ref mut file) => file.read(buf),
+            #[cfg(unix)]
+            StreamSocket::UnixListener(_) => Err(io::Error::new(ErrorKind::InvalidInput, "can't read from a listener")),
+        }
+    }
+}
+
+impl Write for StreamSocket {
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        match *self {
+            StreamSocket::Tcp(ref mut socket) => socket.write(buf),
+            #[cfg(unix)]
+            StreamSocket::Unix(ref mut socket) => socket.write(buf),
+            #[cfg(unix)]
+            StreamSocket::Fd(ref mut file) => file.write(buf),
+            #[cfg(unix)]
+            StreamSocket::UnixListener(_) => Err(io::Error::new(ErrorKind::InvalidInput, "can't write to a listener")),
+        }
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl From<TcpSocket> for StreamSocket {
+    fn from(socket: TcpSocket) -> StreamSocket {
+        StreamSocket::Tcp(socket)
+    }
+}
+
+#[cfg(unix)]
+impl From<UnixStream> for StreamSocket {
+    fn from(socket: UnixStream) -> StreamSocket {
+        StreamSocket::Unix(socket)
+    }
+}
+
+#[cfg(unix)]
+impl From<UnixListener> for StreamSocket {
+    fn from(socket: UnixListener) -> StreamSocket {
+        StreamSocket::UnixListener(socket)
+    }
+}
+
+#[cfg(unix)]
+impl From<File> for StreamSocket {
+    fn from(file: File) -> StreamSocket {
+        StreamSocket::Fd(file)
+    }
+}
+
+#[cfg(unix)]
+fn set_fd_nonblocking(fd: libc::c_int, nonblocking: bool) -> Result<()> {
+    unsafe {
+        let flags = libc::fcntl(fd, libc::F_GETFL);
+        if flags < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let flags = if nonblocking {
+            flags | libc::O_NONBLOCK
+        } else {
+            flags & !libc::O_NONBLOCK
+        };
+        if libc::fcntl(fd, libc::F_SETFL, flags) < 0 {
+            return Err(io::Error::last_os_error());
+        }
+    }
+    Ok(())
+}
+
+/// socket结束的原因, 在结束回调中通过EventBuffer.end_reason获取
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum EndReason {
+    /// 对端关闭, 读到EOF
+    Eof,
+    /// 连接已挂断
+    Hangup,
+    /// 读取出错, 错误记录在EventBuffer.error中
+    ReadError,
+    /// 写入出错, 错误记录在EventBuffer.error中
+    WriteError,
+    /// socket出错, 错误为SO_ERROR的值
+    SocketError,
+    /// 异步连接失败或超时
+    ConnectFailed,
+    /// 读空闲, 写阻塞或存活时间超时
+    Timeout,
+    /// 回调返回了RetValue::OVER
+    Over,
+    /// 修改epoll的监听失败
+    ModifyFailed,
+    /// 本地调用unregister_socket关闭
+    Unregister,
+    /// 写缓冲中的数据发送完毕后本地关闭, 见EventLoop::close_after_flush
+    Flushed,
+    /// 读缓冲超过上限, 见EventEntry::with_read_limit
+    ReadOverflow,
+}
+
 /// 每个已分配的Socket在事件管理器都会分配一个该对象以维护已读队列, 
 /// 在写队列, 标记是否正在请求写操作, 是否发生错误, 及Socket变量
 #[derive(Debug)]
 pub struct EventBuffer {
     pub read: Buffer,
     pub write: Buffer,
-    pub socket: TcpSocket,
+    pub socket: StreamSocket,
     pub read_cache: Vec<u8>,
     pub is_in_write: bool,
     pub is_in_read: bool,
     pub error: Result<()>,
+    /// socket结束的原因, FLAG_ERROR为出错, FLAG_ENDED为对端关闭, FLAG_TIMEOUT为超时
+    pub end_flags: EventFlags,
+    /// socket结束的具体原因, 在结束回调中总为Some
+    pub end_reason: Option<EndReason>,
 }
 
 impl EventBuffer {
-    pub fn new(socket: TcpSocket, capacity: usize) -> EventBuffer {
+    pub fn new<S: Into<StreamSocket>>(socket: S, capacity: usize) -> EventBuffer {
         let capacity = cmp::max(capacity, 1024);
         EventBuffer {
             read: Buffer::new(),
             write: Buffer::new(),
-            socket: socket,
+            socket: socket.into(),
             read_cache: vec![0; capacity],
             is_in_write: false,
             is_in_read: false,
             error: Ok(()), 
+            end_flags: EventFlags::empty(),
+            end_reason: None,
         }
     }
 
+    /// socket为TCP时返回该TcpSocket, socket字段由TcpSocket改为StreamSocket后, 原先直接访问buffer.socket的代码可改用此方法
+    pub fn tcp_socket(&self) -> Option<&TcpSocket> {
+        self.socket.as_tcp()
+    }
+
+    pub fn tcp_socket_mut(&mut self) -> Option<&mut TcpSocket> {
+        self.socket.as_tcp_mut()
+    }
+
     pub fn as_raw_socket(&self) -> psocket::SOCKET {
         self.socket.as_raw_socket()
     }
@@ -155,4 +392,17 @@ impl EventBuffer {
     pub fn has_read_buffer(&self) -> bool {
         !self.read.empty()
     }
+
+    /// 记录socket结束的原因, 已有原因时保留最先记录的
+    pub fn set_end_reason(&mut self, reason: EndReason) {
+        if self.end_reason.is_none() {
+            self.end_reason = Some(reason);
+        }
+    }
+
+    /// 记录socket的错误, 并标记FLAG_ERROR
+    pub fn set_error(&mut self, err: io::Error) {
+        self.error = Err(err);
+        self.end_flags.insert(EventFlags::FLAG_ERROR);
+    }
 }
@@ -1,11 +1,14 @@
-pub use {EventFlags, EventLoop, RetValue, EventBuffer, now_micro};
+pub use {EventFlags, EventLoop, RetValue, EventBuffer, now_monotonic, wall_to_monotonic};
 use std::fmt;
 use std::cell::Cell;
-use std::cmp::{Ord, Ordering};
+use std::cmp::{self, Ord, Ordering};
 use std::hash::{self, Hash};
 use std::any::Any;
-use std::io::Result;
-use psocket::{TcpSocket, SOCKET};
+use std::io::{self, Result};
+use psocket::{TcpSocket, SocketAddr, SOCKET};
+#[cfg(unix)]
+use std::os::unix::net::{UnixStream, SocketAddr as UnixSocketAddr};
+use timer::{TimerId, TimerRepeat, MissedTick};
 
 extern crate time;
 
@@ -20,7 +23,46 @@ pub type EventCb = fn(ev: &mut EventLoop,
                       data: Option<&mut CellAny>)
                       -> RetValue;
 pub type EndCb = fn(ev: &mut EventLoop, &mut EventBuffer, data: Option<CellAny>);
-pub type TimerCb = fn(ev: &mut EventLoop, timer: u32, data: Option<&mut CellAny>) -> (RetValue, u64);
+pub type TimerCb = fn(ev: &mut EventLoop, timer: TimerId, data: Option<&mut CellAny>) -> (RetValue, u64);
+pub type SignalCb = fn(ev: &mut EventLoop, signo: i32, data: Option<&mut CellAny>) -> RetValue;
+pub type ReadyCb = fn(ev: &mut EventLoop, fd: SOCKET, events: EventFlags, data: Option<&mut CellAny>) -> RetValue;
+pub type RecvFromCb = fn(ev: &mut EventLoop,
+                         socket: SOCKET,
+                         buf: &[u8],
+                         addr: SocketAddr,
+                         data: Option<&mut CellAny>)
+                         -> RetValue;
+pub type DatagramErrorCb = fn(ev: &mut EventLoop,
+                             socket: SOCKET,
+                             err: &io::Error,
+                             data: Option<&mut CellAny>)
+                             -> RetValue;
+#[cfg(unix)]
+pub type UnixAcceptCb = fn(ev: &mut EventLoop,
+                           Result<UnixStream>,
+                           data: Option<&mut CellAny>)
+                           -> RetValue;
+#[cfg(unix)]
+pub type UnixRecvFromCb = fn(ev: &mut EventLoop,
+                             socket: SOCKET,
+                             buf: &[u8],
+                             addr: UnixSocketAddr,
+                             data: Option<&mut CellAny>)
+                             -> RetValue;
+
+/// 闭包形式的回调, 状态由闭包自身捕获, 因此不再需要data参数
+pub type AcceptFn = Box<dyn FnMut(&mut EventLoop, Result<TcpSocket>) -> RetValue>;
+pub type EventFn = Box<dyn FnMut(&mut EventLoop, &mut EventBuffer) -> RetValue>;
+pub type EndFn = Box<dyn FnMut(&mut EventLoop, &mut EventBuffer)>;
+pub type TimerFn = Box<dyn FnMut(&mut EventLoop, TimerId) -> (RetValue, u64)>;
+pub type SignalFn = Box<dyn FnMut(&mut EventLoop, i32) -> RetValue>;
+pub type ReadyFn = Box<dyn FnMut(&mut EventLoop, SOCKET, EventFlags) -> RetValue>;
+pub type RecvFromFn = Box<dyn FnMut(&mut EventLoop, SOCKET, &[u8], SocketAddr) -> RetValue>;
+pub type DatagramErrorFn = Box<dyn FnMut(&mut EventLoop, SOCKET, &io::Error) -> RetValue>;
+#[cfg(unix)]
+pub type UnixAcceptFn = Box<dyn FnMut(&mut EventLoop, Result<UnixStream>) -> RetValue>;
+#[cfg(unix)]
+pub type UnixRecvFromFn = Box<dyn FnMut(&mut EventLoop, SOCKET, &[u8], UnixSocketAddr) -> RetValue>;
 
 macro_rules! data_to_cellany {
     ( $x:expr ) => {
@@ -34,38 +76,121 @@ macro_rules! data_to_cellany {
 
 pub struct EventEntry {
     pub ev_fd: SOCKET,
-    pub time_id: u32,
-    pub tick_ms: u64,
+    pub time_id: TimerId,
+    /// 定时器的到期时间, 单调时钟的微秒数
+    pub tick_us: u64,
+    /// 定时器的间隔, 单位为微秒
     pub tick_step: u64,
+    pub timer_repeat: TimerRepeat,
     pub ev_events: EventFlags,
+    /// 读空闲超时, 超过该微秒数未读到数据则关闭socket, 为0表示不限制
+    pub read_timeout: u64,
+    /// 写阻塞超时, 写缓冲中的数据超过该微秒数未能写出则关闭socket, 为0表示不限制
+    pub write_timeout: u64,
+    /// 连接的最长存活时间, 从注册时开始计时, 单位为微秒, 为0表示不限制
+    pub life_timeout: u64,
+    /// 异步连接的超时时间, 单位为微秒, 为0表示不限制
+    pub connect_timeout: u64,
+    /// 写缓冲的高水位及低水位, 单位为字节, 高水位为0表示不限制
+    pub write_high: usize,
+    pub write_low: usize,
+    /// 读缓冲的上限及低水位, 单位为字节, 上限为0表示不限制
+    pub read_max: usize,
+    pub read_low: usize,
+    /// 读缓冲超过上限时暂停读取, 为false时关闭该socket
+    pub read_max_pause: bool,
     pub accept: Option<AcceptCb>,
+    pub connect: Option<EventCb>,
     pub read: Option<EventCb>,
     pub write: Option<EventCb>,
     pub end: Option<EndCb>,
     pub timer: Option<TimerCb>,
+    pub signal: Option<SignalCb>,
+    pub recv_from: Option<RecvFromCb>,
+    pub ready: Option<ReadyCb>,
+    pub read_eof: Option<EventCb>,
+    pub writable: Option<EventCb>,
+    pub datagram_error: Option<DatagramErrorCb>,
+    #[cfg(unix)]
+    pub accept_unix: Option<UnixAcceptCb>,
+    #[cfg(unix)]
+    pub recv_from_unix: Option<UnixRecvFromCb>,
+    pub accept_fn: Option<AcceptFn>,
+    pub connect_fn: Option<EventFn>,
+    pub read_fn: Option<EventFn>,
+    pub write_fn: Option<EventFn>,
+    pub end_fn: Option<EndFn>,
+    pub timer_fn: Option<TimerFn>,
+    pub signal_fn: Option<SignalFn>,
+    pub recv_from_fn: Option<RecvFromFn>,
+    pub ready_fn: Option<ReadyFn>,
+    pub read_eof_fn: Option<EventFn>,
+    pub writable_fn: Option<EventFn>,
+    pub datagram_error_fn: Option<DatagramErrorFn>,
+    #[cfg(unix)]
+    pub accept_unix_fn: Option<UnixAcceptFn>,
+    #[cfg(unix)]
+    pub recv_from_unix_fn: Option<UnixRecvFromFn>,
     pub data: Option<CellAny>,
 }
 
 impl Default for EventEntry {
     fn default() -> EventEntry {
         EventEntry {
-            tick_ms: 0,
+            tick_us: 0,
             tick_step: 0,
+            timer_repeat: TimerRepeat::FixedDelay,
             ev_events: EventFlags::empty(),
+            read_timeout: 0,
+            write_timeout: 0,
+            life_timeout: 0,
+            connect_timeout: 0,
+            write_high: 0,
+            write_low: 0,
+            read_max: 0,
+            read_low: 0,
+            read_max_pause: false,
             accept: None,
+            connect: None,
             read: None,
             write: None,
             end: None,
             timer: None,
+            signal: None,
+            recv_from: None,
+            ready: None,
+            read_eof: None,
+            writable: None,
+            datagram_error: None,
+            #[cfg(unix)]
+            accept_unix: None,
+            #[cfg(unix)]
+            recv_from_unix: None,
+            accept_fn: None,
+            connect_fn: None,
+            read_fn: None,
+            write_fn: None,
+            end_fn: None,
+            timer_fn: None,
+            signal_fn: None,
+            recv_from_fn: None,
+            ready_fn: None,
+            read_eof_fn: None,
+            writable_fn: None,
+            datagram_error_fn: None,
+            #[cfg(unix)]
+            accept_unix_fn: None,
+            #[cfg(unix)]
+            recv_from_unix_fn: None,
             data: None,
-            time_id: 0,
+            time_id: TimerId::default(),
             ev_fd: 0,
         }
     }
 }
 
 impl EventEntry {
-    /// tick_step is us
+    /// tick_step的单位为微秒
     pub fn new_timer(
         tick_step: u64,
         tick_repeat: bool,
@@ -73,7 +198,7 @@ impl EventEntry {
         data: Option<Box<dyn Any>>,
     ) -> EventEntry {
         EventEntry {
-            tick_ms: now_micro() + tick_step,
+            tick_us: now_monotonic() + tick_step,
             tick_step: tick_step,
             ev_events: if tick_repeat {
                 EventFlags::FLAG_TIMEOUT | EventFlags::FLAG_PERSIST
@@ -86,14 +211,37 @@ impl EventEntry {
         }
     }
 
-    /// tick_step is us
+    /// 固定频率的重复定时器, 下次到期时间为上次到期时间加tick_step, tick_step的单位为微秒
+    pub fn new_timer_rate(
+        tick_step: u64,
+        missed: MissedTick,
+        timer: Option<TimerCb>,
+        data: Option<Box<dyn Any>>,
+    ) -> EventEntry {
+        EventEntry {
+            timer_repeat: TimerRepeat::FixedRate(missed),
+            .. EventEntry::new_timer(tick_step, true, timer, data)
+        }
+    }
+
+    /// tick_time为墙上时间, 参见now_micro, 创建时按当前墙上时间换算为单调时钟的时间
+    /// 之后系统时间的调整不再影响该定时器
     pub fn new_timer_at(
         tick_time: u64,
         timer: Option<TimerCb>,
         data: Option<Box<dyn Any>>,
+    ) -> EventEntry {
+        EventEntry::new_timer_at_monotonic(wall_to_monotonic(tick_time), timer, data)
+    }
+
+    /// tick_time为单调时钟的时间, 参见now_monotonic
+    pub fn new_timer_at_monotonic(
+        tick_time: u64,
+        timer: Option<TimerCb>,
+        data: Option<Box<dyn Any>>,
     ) -> EventEntry {
         EventEntry {
-            tick_ms: tick_time,
+            tick_us: tick_time,
             ev_events: EventFlags::FLAG_TIMEOUT,
             timer: timer,
             data: data_to_cellany!(data), 
@@ -137,6 +285,314 @@ impl EventEntry {
         }
     }
 
+    /// 异步连接事件, 连接完成后转为正常的读写事件, timeout为连接的超时微秒数, 为0表示不限制
+    /// 连接成功或失败均回调connect, 失败时EventBuffer.error中为失败的原因, 之后关闭该socket
+    pub fn new_connect(
+        ev_fd: SOCKET,
+        timeout: u64,
+        connect: Option<EventCb>,
+        read: Option<EventCb>,
+        end: Option<EndCb>,
+        data: Option<Box<dyn Any>>,
+    ) -> EventEntry {
+        EventEntry {
+            ev_events: EventFlags::FLAG_CONNECT | EventFlags::FLAG_READ | EventFlags::FLAG_PERSIST,
+            connect_timeout: timeout,
+            connect: connect,
+            read: read,
+            end: end,
+            data: data_to_cellany!(data),
+            ev_fd: ev_fd,
+            .. Default::default()
+        }
+    }
+
+    /// 以闭包作为回调的定时器, tick_step的单位为微秒
+    pub fn new_timer_fn(
+        tick_step: u64,
+        tick_repeat: bool,
+        timer: TimerFn,
+    ) -> EventEntry {
+        EventEntry {
+            tick_us: now_monotonic() + tick_step,
+            tick_step: tick_step,
+            ev_events: if tick_repeat {
+                EventFlags::FLAG_TIMEOUT | EventFlags::FLAG_PERSIST
+            } else {
+                EventFlags::FLAG_TIMEOUT
+            },
+            timer_fn: Some(timer),
+            .. Default::default()
+        }
+    }
+
+    /// 以闭包作为回调的固定频率重复定时器, tick_step的单位为微秒
+    pub fn new_timer_rate_fn(
+        tick_step: u64,
+        missed: MissedTick,
+        timer: TimerFn,
+    ) -> EventEntry {
+        EventEntry {
+            timer_repeat: TimerRepeat::FixedRate(missed),
+            .. EventEntry::new_timer_fn(tick_step, true, timer)
+        }
+    }
+
+    /// 以闭包作为回调的socket事件
+    pub fn new_event_fn(
+        ev_fd: SOCKET,
+        ev_events: EventFlags,
+        read: Option<EventFn>,
+        write: Option<EventFn>,
+        end: Option<EndFn>,
+    ) -> EventEntry {
+        EventEntry {
+            ev_events: ev_events,
+            read_fn: read,
+            write_fn: write,
+            end_fn: end,
+            ev_fd: ev_fd,
+            .. Default::default()
+        }
+    }
+
+    /// 以闭包作为回调的监听事件
+    pub fn new_accept_fn(
+        ev_fd: SOCKET,
+        ev_events: EventFlags,
+        accept: Option<AcceptFn>,
+        end: Option<EndFn>,
+    ) -> EventEntry {
+        EventEntry {
+            ev_events: ev_events,
+            accept_fn: accept,
+            end_fn: end,
+            ev_fd: ev_fd,
+            .. Default::default()
+        }
+    }
+
+    /// 以闭包作为回调的异步连接事件, 参数含义同new_connect
+    pub fn new_connect_fn(
+        ev_fd: SOCKET,
+        timeout: u64,
+        connect: Option<EventFn>,
+        read: Option<EventFn>,
+        end: Option<EndFn>,
+    ) -> EventEntry {
+        EventEntry {
+            ev_events: EventFlags::FLAG_CONNECT | EventFlags::FLAG_READ | EventFlags::FLAG_PERSIST,
+            connect_timeout: timeout,
+            connect_fn: connect,
+            read_fn: read,
+            end_fn: end,
+            ev_fd: ev_fd,
+            .. Default::default()
+        }
+    }
+
+    /// 信号事件, 信号值由注册时指定
+    pub fn new_signal(
+        signal: Option<SignalCb>,
+        data: Option<Box<dyn Any>>,
+    ) -> EventEntry {
+        EventEntry {
+            ev_events: EventFlags::FLAG_SIGNAL | EventFlags::FLAG_PERSIST,
+            signal: signal,
+            data: data_to_cellany!(data),
+            .. Default::default()
+        }
+    }
+
+    /// 以闭包作为回调的信号事件
+    pub fn new_signal_fn(signal: SignalFn) -> EventEntry {
+        EventEntry {
+            ev_events: EventFlags::FLAG_SIGNAL | EventFlags::FLAG_PERSIST,
+            signal_fn: Some(signal),
+            .. Default::default()
+        }
+    }
+
+    /// 设置socket的读空闲超时, 写阻塞超时及最长存活时间, 单位均为微秒, 为0表示不限制
+    /// 超时后主循环关闭该socket, 并以TimedOut错误调用结束回调, 目前仅epoll实现
+    pub fn with_timeouts(mut self, read_timeout: u64, write_timeout: u64, life_timeout: u64) -> EventEntry {
+        self.read_timeout = read_timeout;
+        self.write_timeout = write_timeout;
+        self.life_timeout = life_timeout;
+        self
+    }
+
+    /// 设置对端半关闭时的回调, 读到EOF后停止读取并回调read_eof, socket保持注册以继续写入
+    /// 通过shutdown_write关闭写端后结束该socket, 未设置时读到EOF即关闭socket, 目前仅epoll实现
+    pub fn with_read_eof(mut self, read_eof: EventCb) -> EventEntry {
+        self.read_eof = Some(read_eof);
+        self
+    }
+
+    pub fn with_read_eof_fn(mut self, read_eof: EventFn) -> EventEntry {
+        self.read_eof_fn = Some(read_eof);
+        self
+    }
+
+    /// 设置写缓冲的高水位及低水位, 单位为字节
+    /// 写缓冲达到高水位后send_socket返回WouldBlock错误且不再缓存数据, 降到低水位及以下时回调writable, 目前仅epoll实现
+    pub fn with_write_watermarks(mut self, high: usize, low: usize) -> EventEntry {
+        self.write_high = high;
+        self.write_low = cmp::min(low, high);
+        self
+    }
+
+    /// 设置读缓冲的上限, 单位为字节, 读回调之后读缓冲仍达到上限时
+    /// pause为true则暂停读取, 取走数据后由EventLoop::resume_read恢复, 否则以EndReason::ReadOverflow关闭该socket, 目前仅epoll实现
+    pub fn with_read_limit(mut self, max: usize, pause: bool) -> EventEntry {
+        self.read_max = max;
+        self.read_max_pause = pause;
+        self
+    }
+
+    /// 设置读缓冲的低水位, 单位为字节, 读缓冲中的数据达到低水位后才回调read, 目前仅epoll实现
+    pub fn with_read_low(mut self, low: usize) -> EventEntry {
+        self.read_low = low;
+        self
+    }
+
+    /// 设置写缓冲从高水位降到低水位时的回调, 发送方可在此继续发送
+    pub fn with_writable(mut self, writable: EventCb) -> EventEntry {
+        self.writable = Some(writable);
+        self
+    }
+
+    pub fn with_writable_fn(mut self, writable: EventFn) -> EventEntry {
+        self.writable_fn = Some(writable);
+        self
+    }
+
+    /// 设置数据报socket出错时的回调, 如收发失败或ICMP不可达等异步错误, 回调返回RetValue::OVER则移除该监听
+    /// 未设置时错误保留在该socket上, 可由EventLoop::take_datagram_error取出, 目前仅epoll实现
+    pub fn with_datagram_error(mut self, datagram_error: DatagramErrorCb) -> EventEntry {
+        self.datagram_error = Some(datagram_error);
+        self
+    }
+
+    pub fn with_datagram_error_fn(mut self, datagram_error: DatagramErrorFn) -> EventEntry {
+        self.datagram_error_fn = Some(datagram_error);
+        self
+    }
+
+    /// UDP事件, 每收到一个数据报回调一次recv_from
+    pub fn new_udp(
+        ev_fd: SOCKET,
+        recv_from: Option<RecvFromCb>,
+        data: Option<Box<dyn Any>>,
+    ) -> EventEntry {
+        EventEntry {
+            ev_events: EventFlags::FLAG_READ | EventFlags::FLAG_PERSIST,
+            recv_from: recv_from,
+            data: data_to_cellany!(data),
+            ev_fd: ev_fd,
+            .. Default::default()
+        }
+    }
+
+    /// 以闭包作为回调的UDP事件
+    pub fn new_udp_fn(ev_fd: SOCKET, recv_from: RecvFromFn) -> EventEntry {
+        EventEntry {
+            ev_events: EventFlags::FLAG_READ | EventFlags::FLAG_PERSIST,
+            recv_from_fn: Some(recv_from),
+            ev_fd: ev_fd,
+            .. Default::default()
+        }
+    }
+
+    /// 任意句柄的就绪事件, 仅通知可读可写等就绪状态, 由调用者自行读写
+    pub fn new_fd(
+        ev_fd: SOCKET,
+        ev_events: EventFlags,
+        ready: Option<ReadyCb>,
+        data: Option<Box<dyn Any>>,
+    ) -> EventEntry {
+        EventEntry {
+            ev_events: ev_events,
+            ready: ready,
+            data: data_to_cellany!(data),
+            ev_fd: ev_fd,
+            .. Default::default()
+        }
+    }
+
+    /// 以闭包作为回调的句柄就绪事件
+    pub fn new_fd_fn(ev_fd: SOCKET, ev_events: EventFlags, ready: ReadyFn) -> EventEntry {
+        EventEntry {
+            ev_events: ev_events,
+            ready_fn: Some(ready),
+            ev_fd: ev_fd,
+            .. Default::default()
+        }
+    }
+
+    /// Unix域socket的监听事件
+    #[cfg(unix)]
+    pub fn new_unix_accept(
+        ev_fd: SOCKET,
+        ev_events: EventFlags,
+        accept: Option<UnixAcceptCb>,
+        end: Option<EndCb>,
+        data: Option<Box<dyn Any>>,
+    ) -> EventEntry {
+        EventEntry {
+            ev_events: ev_events,
+            accept_unix: accept,
+            end: end,
+            data: data_to_cellany!(data),
+            ev_fd: ev_fd,
+            .. Default::default()
+        }
+    }
+
+    /// 以闭包作为回调的Unix域socket监听事件
+    #[cfg(unix)]
+    pub fn new_unix_accept_fn(
+        ev_fd: SOCKET,
+        ev_events: EventFlags,
+        accept: Option<UnixAcceptFn>,
+        end: Option<EndFn>,
+    ) -> EventEntry {
+        EventEntry {
+            ev_events: ev_events,
+            accept_unix_fn: accept,
+            end_fn: end,
+            ev_fd: ev_fd,
+            .. Default::default()
+        }
+    }
+
+    /// Unix域数据报事件, 每收到一个数据报回调一次recv_from
+    #[cfg(unix)]
+    pub fn new_unix_datagram(
+        ev_fd: SOCKET,
+        recv_from: Option<UnixRecvFromCb>,
+        data: Option<Box<dyn Any>>,
+    ) -> EventEntry {
+        EventEntry {
+            ev_events: EventFlags::FLAG_READ | EventFlags::FLAG_PERSIST,
+            recv_from_unix: recv_from,
+            data: data_to_cellany!(data),
+            ev_fd: ev_fd,
+            .. Default::default()
+        }
+    }
+
+    /// 以闭包作为回调的Unix域数据报事件
+    #[cfg(unix)]
+    pub fn new_unix_datagram_fn(ev_fd: SOCKET, recv_from: UnixRecvFromFn) -> EventEntry {
+        EventEntry {
+            ev_events: EventFlags::FLAG_READ | EventFlags::FLAG_PERSIST,
+            recv_from_unix_fn: Some(recv_from),
+            ev_fd: ev_fd,
+            .. Default::default()
+        }
+    }
+
     pub fn new_evfd(ev_fd: SOCKET, ev_events: EventFlags) -> EventEntry {
         EventEntry {
             ev_events: ev_events,
@@ -146,6 +602,9 @@ impl EventEntry {
     }
 
     pub fn accept_cb(&mut self, ev: &mut EventLoop, tcp: Result<TcpSocket>) -> RetValue {
+        if let Some(ref mut accept) = self.accept_fn {
+            return accept(ev, tcp);
+        }
         if self.accept.is_none() {
             return RetValue::OK;
         }
@@ -153,7 +612,33 @@ impl EventEntry {
         self.accept.unwrap()(ev, tcp, self.data.as_mut())
     }
 
+    #[cfg(unix)]
+    pub fn accept_unix_cb(&mut self, ev: &mut EventLoop, stream: Result<UnixStream>) -> RetValue {
+        if let Some(ref mut accept) = self.accept_unix_fn {
+            return accept(ev, stream);
+        }
+        if self.accept_unix.is_none() {
+            return RetValue::OK;
+        }
+
+        self.accept_unix.unwrap()(ev, stream, self.data.as_mut())
+    }
+
+    pub fn connect_cb(&mut self, ev: &mut EventLoop, event: &mut EventBuffer) -> RetValue {
+        if let Some(ref mut connect) = self.connect_fn {
+            return connect(ev, event);
+        }
+        if self.connect.is_none() {
+            return RetValue::OK;
+        }
+
+        self.connect.unwrap()(ev, event, self.data.as_mut())
+    }
+
     pub fn read_cb(&mut self, ev: &mut EventLoop, event: &mut EventBuffer) -> RetValue {
+        if let Some(ref mut read) = self.read_fn {
+            return read(ev, event);
+        }
         if self.read.is_none() {
             return RetValue::OK;
         }
@@ -162,6 +647,9 @@ impl EventEntry {
     }
     
     pub fn write_cb(&mut self, ev: &mut EventLoop, event: &mut EventBuffer) -> RetValue {
+        if let Some(ref mut write) = self.write_fn {
+            return write(ev, event);
+        }
         if self.write.is_none() {
             return RetValue::OK;
         }
@@ -169,7 +657,10 @@ impl EventEntry {
         self.write.unwrap()(ev, event, self.data.as_mut())
     }
 
-    pub fn timer_cb(&mut self, ev: &mut EventLoop, timer: u32) -> (RetValue, u64) {
+    pub fn timer_cb(&mut self, ev: &mut EventLoop, timer: TimerId) -> (RetValue, u64) {
+        if let Some(ref mut timer_fn) = self.timer_fn {
+            return timer_fn(ev, timer);
+        }
         if self.timer.is_none() {
             return (RetValue::OK, 0);
         }
@@ -181,7 +672,92 @@ impl EventEntry {
         ret
     }
 
+    pub fn signal_cb(&mut self, ev: &mut EventLoop, signo: i32) -> RetValue {
+        if let Some(ref mut signal) = self.signal_fn {
+            return signal(ev, signo);
+        }
+        if self.signal.is_none() {
+            return RetValue::OK;
+        }
+
+        self.signal.unwrap()(ev, signo, self.data.as_mut())
+    }
+
+    pub fn recv_from_cb(&mut self, ev: &mut EventLoop, socket: SOCKET, buf: &[u8], addr: SocketAddr) -> RetValue {
+        if let Some(ref mut recv_from) = self.recv_from_fn {
+            return recv_from(ev, socket, buf, addr);
+        }
+        if self.recv_from.is_none() {
+            return RetValue::OK;
+        }
+
+        self.recv_from.unwrap()(ev, socket, buf, addr, self.data.as_mut())
+    }
+
+    #[cfg(unix)]
+    pub fn recv_from_unix_cb(&mut self, ev: &mut EventLoop, socket: SOCKET, buf: &[u8], addr: UnixSocketAddr) -> RetValue {
+        if let Some(ref mut recv_from) = self.recv_from_unix_fn {
+            return recv_from(ev, socket, buf, addr);
+        }
+        if self.recv_from_unix.is_none() {
+            return RetValue::OK;
+        }
+
+        self.recv_from_unix.unwrap()(ev, socket, buf, addr, self.data.as_mut())
+    }
+
+    pub fn datagram_error_cb(&mut self, ev: &mut EventLoop, socket: SOCKET, err: &io::Error) -> RetValue {
+        if let Some(ref mut datagram_error) = self.datagram_error_fn {
+            return datagram_error(ev, socket, err);
+        }
+        if self.datagram_error.is_none() {
+            return RetValue::OK;
+        }
+
+        self.datagram_error.unwrap()(ev, socket, err, self.data.as_mut())
+    }
+
+    pub fn ready_cb(&mut self, ev: &mut EventLoop, fd: SOCKET, events: EventFlags) -> RetValue {
+        if let Some(ref mut ready) = self.ready_fn {
+            return ready(ev, fd, events);
+        }
+        if self.ready.is_none() {
+            return RetValue::OK;
+        }
+
+        self.ready.unwrap()(ev, fd, events, self.data.as_mut())
+    }
+
+    pub fn has_read_eof(&self) -> bool {
+        self.read_eof.is_some() || self.read_eof_fn.is_some()
+    }
+
+    pub fn read_eof_cb(&mut self, ev: &mut EventLoop, event: &mut EventBuffer) -> RetValue {
+        if let Some(ref mut read_eof) = self.read_eof_fn {
+            return read_eof(ev, event);
+        }
+        if self.read_eof.is_none() {
+            return RetValue::OK;
+        }
+
+        self.read_eof.unwrap()(ev, event, self.data.as_mut())
+    }
+
+    pub fn writable_cb(&mut self, ev: &mut EventLoop, event: &mut EventBuffer) -> RetValue {
+        if let Some(ref mut writable) = self.writable_fn {
+            return writable(ev, event);
+        }
+        if self.writable.is_none() {
+            return RetValue::OK;
+        }
+
+        self.writable.unwrap()(ev, event, self.data.as_mut())
+    }
+
     pub fn end_cb(&mut self, ev: &mut EventLoop, event: &mut EventBuffer) {
+        if let Some(mut end) = self.end_fn.take() {
+            return end(ev, event);
+        }
         if self.end.is_none() {
             return;
         }
@@ -198,33 +774,59 @@ impl EventEntry {
             if event.has_flag(EventFlags::FLAG_READ) || event.has_flag(EventFlags::FLAG_ACCEPT) {
                 self.ev_events.remove(EventFlags::FLAG_READ);
                 self.read = None;
+                self.read_fn = None;
             }
             if event.has_flag(EventFlags::FLAG_ACCEPT) {
                 self.ev_events.remove(EventFlags::FLAG_ACCEPT);
                 self.accept = None;
+                self.accept_fn = None;
+                #[cfg(unix)]
+                {
+                    self.accept_unix = None;
+                    self.accept_unix_fn = None;
+                }
             }
             if event.has_flag(EventFlags::FLAG_WRITE) {
                 self.ev_events.remove(EventFlags::FLAG_WRITE);
                 self.write = None;
+                self.write_fn = None;
             }
         } else {
-            if event.has_flag(EventFlags::FLAG_READ) {
+            if event.ev_events.contains(EventFlags::FLAG_READ) {
                 self.ev_events.insert(EventFlags::FLAG_READ);
                 if event.read.is_some() {
                     self.read = event.read;
                 }
+                if event.read_fn.is_some() {
+                    self.read_fn = event.read_fn;
+                }
             }
-            if event.has_flag(EventFlags::FLAG_ACCEPT) {
+            if event.ev_events.contains(EventFlags::FLAG_ACCEPT) {
                 self.ev_events.insert(EventFlags::FLAG_ACCEPT);
                 if event.accept.is_some() {
                     self.accept = event.accept;
                 }
+                if event.accept_fn.is_some() {
+                    self.accept_fn = event.accept_fn;
+                }
+                #[cfg(unix)]
+                {
+                    if event.accept_unix.is_some() {
+                        self.accept_unix = event.accept_unix;
+                    }
+                    if event.accept_unix_fn.is_some() {
+                        self.accept_unix_fn = event.accept_unix_fn;
+                    }
+                }
             }
-            if event.has_flag(EventFlags::FLAG_WRITE) {
+            if event.ev_events.contains(EventFlags::FLAG_WRITE) {
                 self.ev_events.insert(EventFlags::FLAG_WRITE);
                 if event.write.is_some() {
                     self.write = event.write;
                 }
+                if event.write_fn.is_some() {
+                    self.write_fn = event.write_fn;
+                }
             }
         }
     }
@@ -235,9 +837,9 @@ impl fmt::Debug for EventEntry {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(
             f,
-            "ev_fd = {}, tick_ms = {}, tick_step = {}, ev_events = {:?}",
+            "ev_fd = {}, tick_us = {}, tick_step = {}, ev_events = {:?}",
             self.ev_fd,
-            self.tick_ms,
+            self.tick_us,
             self.tick_step,
             self.ev_events
         )
@@ -246,20 +848,20 @@ impl fmt::Debug for EventEntry {
 
 impl Ord for EventEntry {
     fn cmp(&self, other: &Self) -> Ordering {
-        other.tick_ms.cmp(&self.tick_ms)
+        other.tick_us.cmp(&self.tick_us)
     }
 }
 
 impl PartialOrd for EventEntry {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(other.tick_ms.cmp(&self.tick_ms))
+        Some(other.tick_us.cmp(&self.tick_us))
     }
 }
 
 impl PartialEq for EventEntry {
     fn eq(&self, other: &Self) -> bool {
         if self.ev_events.contains(EventFlags::FLAG_TIMEOUT) {
-            self.tick_ms == other.tick_ms
+            self.tick_us == other.tick_us
         } else {
             self.ev_fd == other.ev_fd
         }
@@ -9,5 +9,8 @@ bitflags! {
             const FLAG_ENDED            = 0b000001000000;
             const FLAG_READ_PERSIST     = 0b000010000000;
             const FLAG_WRITE_PERSIST    = 0b000100000000;
+            const FLAG_SIGNAL           = 0b001000000000;
+            const FLAG_CONNECT          = 0b010000000000;
+            const FLAG_EDGE             = 0b100000000000;
         }
     }
@@ -0,0 +1,66 @@
+use std::io;
+use std::mem;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use sys::Awakener;
+use EventLoop;
+
+/// 投递到主循环线程执行的任务
+pub type Task = Box<dyn FnOnce(&mut EventLoop) + Send>;
+
+struct HandleInner {
+    awakener: Arc<Awakener>,
+    tasks: Mutex<Vec<Task>>,
+    shutdown: AtomicBool,
+}
+
+/// 主循环的跨线程句柄, 可在其它线程中唤醒主循环, 投递任务到主循环线程执行, 或者请求关闭主循环
+#[derive(Clone)]
+pub struct EventLoopHandle {
+    inner: Arc<HandleInner>,
+}
+
+impl EventLoopHandle {
+    #[doc(hidden)]
+    pub fn new(awakener: Arc<Awakener>) -> EventLoopHandle {
+        EventLoopHandle {
+            inner: Arc::new(HandleInner {
+                awakener: awakener,
+                tasks: Mutex::new(Vec::new()),
+                shutdown: AtomicBool::new(false),
+            }),
+        }
+    }
+
+    /// 唤醒阻塞中的主循环
+    pub fn wakeup(&self) -> io::Result<()> {
+        self.inner.awakener.wakeup()
+    }
+
+    /// 投递任务, 该任务将在主循环线程的下一次循环中执行
+    pub fn post<F>(&self, task: F) -> io::Result<()>
+    where
+        F: FnOnce(&mut EventLoop) + Send + 'static,
+    {
+        self.inner.tasks.lock().unwrap().push(Box::new(task));
+        self.wakeup()
+    }
+
+    /// 请求关闭主循环, 主循环被唤醒后退出
+    pub fn shutdown(&self) -> io::Result<()> {
+        self.inner.shutdown.store(true, Ordering::SeqCst);
+        self.wakeup()
+    }
+
+    /// 取出所有已投递的任务
+    #[doc(hidden)]
+    pub fn take_tasks(&self) -> Vec<Task> {
+        mem::replace(&mut *self.inner.tasks.lock().unwrap(), Vec::new())
+    }
+
+    /// 判断是否有请求关闭主循环, 并清除该请求
+    #[doc(hidden)]
+    pub fn take_shutdown(&self) -> bool {
+        self.inner.shutdown.swap(false, Ordering::SeqCst)
+    }
+}
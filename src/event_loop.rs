@@ -1,39 +1,68 @@
 #![allow(dead_code)]
-use {Timer, EventEntry, now_micro};
+use {Timer, TimerId, TimerBackend, MissedTick, EventEntry, now_monotonic};
 use sys::Selector;
-use {EventFlags, EventBuffer, TimerCb, AcceptCb, EventCb, EndCb};
+#[cfg(any(target_os = "linux", target_os = "android"))]
+use EventLoopHandle;
+use {EventFlags, EventBuffer, StreamSocket, TimerCb, AcceptCb, EventCb, EndCb};
+use {TimerFn, AcceptFn, EventFn, EndFn};
+#[cfg(any(target_os = "linux", target_os = "android"))]
+use {SignalCb, SignalFn, RecvFromCb, RecvFromFn, ReadyCb, ReadyFn};
+#[cfg(any(target_os = "linux", target_os = "android"))]
+use psocket::UdpSocket;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+use {UnixAcceptCb, UnixAcceptFn, UnixRecvFromCb, UnixRecvFromFn};
+#[cfg(any(target_os = "linux", target_os = "android"))]
+use std::os::unix::net::{UnixListener, UnixDatagram, SocketAddr as UnixSocketAddr};
+#[cfg(any(target_os = "linux", target_os = "android"))]
+use sys::connect_unix_asyn;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+use std::os::unix::io::{AsRawFd, RawFd, OwnedFd};
+#[cfg(any(target_os = "linux", target_os = "android"))]
+use std::fs::File;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+use std::process::{Command, Stdio};
+#[cfg(any(target_os = "linux", target_os = "android"))]
+use process::{ChildProcess, ExitFn, ProcessEntry, Processes};
+#[cfg(any(target_os = "linux", target_os = "android"))]
+use libc;
 use std::io;
+use std::cmp;
 use std::any::Any;
-use psocket::{TcpSocket, SOCKET};
+use psocket::{TcpSocket, SocketAddr, SOCKET};
 
 ///回调的函数返回值, 如果返回OK和CONTINUE, 则默认处理
 ///如果返回OVER则主动结束循环, 比如READ则停止READ, 定时器如果是循环的则主动停止当前的定时器 
+///socket的回调返回FLUSH时停止读取, 等写缓冲中的数据发送完毕后关闭该socket, 目前仅epoll实现
 pub enum RetValue {
     OK,
     CONTINUE,
     OVER,
+    FLUSH,
 }
 
 /// Configure EventLoop runtime details
 #[derive(Copy, Clone, Debug)]
 pub struct EventLoopConfig {
-    pub io_poll_timeout_ms: usize,
+    /// 单次等待IO的最长时间, 为None时一直阻塞到最近的定时器到期或有IO事件
+    pub io_poll_timeout_ms: Option<usize>,
 
     pub select_catacity: usize,
     pub buffer_capacity: usize,
 
     // == Timer ==
     pub time_max_id: u32,
+    pub timer_backend: TimerBackend,
 }
 
 impl Default for EventLoopConfig {
     fn default() -> EventLoopConfig {
         EventLoopConfig {
-            io_poll_timeout_ms: 1,
+            io_poll_timeout_ms: None,
 
             select_catacity: 1024,
             buffer_capacity: 65_536,
             time_max_id: u32::max_value() / 2,
+            timer_backend: TimerBackend::Tree,
         }
     }
 }
@@ -45,6 +74,10 @@ pub struct EventLoop {
     timer: Timer,
     pub selector: Selector,
     config: EventLoopConfig,
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    handle: EventLoopHandle,
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    processes: Processes,
 }
 
 
@@ -54,16 +87,26 @@ impl EventLoop {
     }
 
     pub fn configured(config: EventLoopConfig) -> io::Result<EventLoop> {
-        let timer = Timer::new(config.time_max_id);
+        let timer = Timer::with_backend(config.time_max_id, config.timer_backend);
         let selector = Selector::new(config.select_catacity)?;
         Ok(EventLoop {
             run: true,
             timer: timer,
+            #[cfg(any(target_os = "linux", target_os = "android"))]
+            handle: EventLoopHandle::new(selector.awakener()),
+            #[cfg(any(target_os = "linux", target_os = "android"))]
+            processes: Processes::new(),
             selector: selector,
             config: config,
         })
     }
 
+    /// 获取主循环的跨线程句柄, 可在其它线程唤醒主循环, 投递任务或者关闭主循环
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn handle(&self) -> EventLoopHandle {
+        self.handle.clone()
+    }
+
     /// 关闭主循环, 将在下一次逻辑执行时退出主循环
     pub fn shutdown(&mut self) {
         self.run = false;
@@ -80,43 +123,75 @@ impl EventLoop {
         self.run = true;
 
         while self.run {
-            // 该此循环中, 没有任何数据得到处理, 则强制cpu休眠1ms, 以防止cpu跑满100%
-            if !self.run_once()? {
-                ::std::thread::sleep(::std::time::Duration::from_millis(1));
-            }
-
+            self.run_once()?;
         }
         Ok(())
     }
 
     /// 进行一次的数据处理, 处理包括处理sockets信息, 及处理定时器的信息
+    /// 等待IO的时间由最近的定时器决定, 因此空闲时不会占用cpu
     pub fn run_once(&mut self) -> io::Result<bool> {
-        let timeout_ms = self.config.io_poll_timeout_ms;
-        let size = Selector::do_select(self, timeout_ms)?;
+        let timeout_us = self.poll_timeout();
+        let size = Selector::do_select(self, timeout_us)?;
         let is_op = self.timer_process();
-        Ok(size != 0 || !is_op)
+        let is_task = self.task_process();
+        Ok(size != 0 || is_op || is_task)
+    }
+
+    /// 执行其它线程通过EventLoopHandle投递的任务, 并响应关闭请求
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    fn task_process(&mut self) -> bool {
+        let tasks = self.handle.take_tasks();
+        let is_op = !tasks.is_empty();
+        for task in tasks {
+            task(self);
+        }
+        if self.handle.take_shutdown() {
+            self.shutdown();
+        }
+        is_op
+    }
+
+    #[cfg(not(any(target_os = "linux", target_os = "android")))]
+    fn task_process(&mut self) -> bool {
+        false
+    }
+
+    /// 计算本次等待IO的超时时间(微秒), 即距离最近的定时器到期的时间, 没有定时器则返回None一直阻塞
+    /// 如果配置了io_poll_timeout_ms, 则不超过该值
+    fn poll_timeout(&self) -> Option<u64> {
+        let timeout = self.timer.tick_first().map(|tick_us| {
+            let now = now_monotonic();
+            if tick_us > now { tick_us - now } else { 0 }
+        });
+        let max = self.config.io_poll_timeout_ms.map(|ms| ms as u64 * 1000);
+        match (timeout, max) {
+            (Some(timeout), Some(max)) => Some(cmp::min(timeout, max)),
+            (Some(timeout), None) => Some(timeout),
+            (None, max) => max,
+        }
     }
 
     /// 根据socket构造EventBuffer
-    pub fn new_buff(&self, socket: TcpSocket) -> EventBuffer {
+    pub fn new_buff<S: Into<StreamSocket>>(&self, socket: S) -> EventBuffer {
         EventBuffer::new(socket, self.config.buffer_capacity)
     }
 
     /// 添加定时器, 如果time_step为0, 则添加定时器失败
-    pub fn add_timer(&mut self, entry: EventEntry) -> u32 {
+    pub fn add_timer(&mut self, entry: EventEntry) -> io::Result<TimerId> {
         self.timer.add_timer(entry)
     }
 
-    /// 添加定时器,  tick_step变量表示每隔多少ms调用一次该回调
-    /// tick_repeat变量表示该定时器是否重复, 如果为true, 则会每tick_step ms进行调用一次, 直到回调返回RetValue::OVER, 或者被主动删除该定时器
-    /// 添加定时器, 如果time_step为0, 则添加定时器失败
+    /// 添加定时器,  tick_step变量表示每隔多少微秒调用一次该回调
+    /// tick_repeat变量表示该定时器是否重复, 如果为true, 则会每tick_step微秒进行调用一次, 直到回调返回RetValue::OVER, 或者被主动删除该定时器
+    /// 返回的定时器句柄在定时器结束后即失效, 对失效句柄的操作不会影响其它定时器
     pub fn add_new_timer(
         &mut self,
         tick_step: u64,
         tick_repeat: bool,
         timer_cb: Option<TimerCb>,
         data: Option<Box<dyn Any>>,
-    ) -> u32 {
+    ) -> io::Result<TimerId> {
         self.timer.add_first_timer(EventEntry::new_timer(
             tick_step,
             tick_repeat,
@@ -125,13 +200,59 @@ impl EventLoop {
         ))
     }
 
-    /// 添加定时器,  tick_time指定某一时间添加触发定时器
+    /// 添加闭包形式的定时器, 参数含义同add_new_timer, 状态由闭包自身捕获
+    pub fn add_new_timer_fn(
+        &mut self,
+        tick_step: u64,
+        tick_repeat: bool,
+        timer_fn: TimerFn,
+    ) -> io::Result<TimerId> {
+        self.timer.add_first_timer(EventEntry::new_timer_fn(
+            tick_step,
+            tick_repeat,
+            timer_fn,
+        ))
+    }
+
+    /// 添加固定频率的重复定时器, 第n次的到期时间为首次到期时间加n * tick_step, 不受回调耗时的影响
+    /// missed指定主循环延迟导致错过到期时间时的处理方式
+    pub fn add_new_timer_rate(
+        &mut self,
+        tick_step: u64,
+        missed: MissedTick,
+        timer_cb: Option<TimerCb>,
+        data: Option<Box<dyn Any>>,
+    ) -> io::Result<TimerId> {
+        self.timer.add_first_timer(EventEntry::new_timer_rate(
+            tick_step,
+            missed,
+            timer_cb,
+            data,
+        ))
+    }
+
+    /// 添加闭包形式的固定频率重复定时器, 参数含义同add_new_timer_rate
+    pub fn add_new_timer_rate_fn(
+        &mut self,
+        tick_step: u64,
+        missed: MissedTick,
+        timer_fn: TimerFn,
+    ) -> io::Result<TimerId> {
+        self.timer.add_first_timer(EventEntry::new_timer_rate_fn(
+            tick_step,
+            missed,
+            timer_fn,
+        ))
+    }
+
+    /// 添加定时器,  tick_time指定某一墙上时间添加触发定时器, 该时间同now_micro
+    /// 添加时按当前墙上时间换算为单调时钟的时间, 之后系统时间的调整不再影响该定时器
     pub fn add_new_timer_at(
         &mut self,
         tick_time: u64,
         timer_cb: Option<TimerCb>,
         data: Option<Box<dyn Any>>,
-    ) -> u32 {
+    ) -> io::Result<TimerId> {
         self.timer.add_first_timer(EventEntry::new_timer_at(
             tick_time,
             timer_cb,
@@ -139,24 +260,86 @@ impl EventLoop {
         ))
     }
 
-    /// 删除指定的定时器id, 定时器内部实现细节为红黑树, 删除定时器的时间为O(logn), 如果存在该定时器, 则返回相关的定时器信息
-    pub fn del_timer(&mut self, time_id: u32) -> Option<EventEntry> {
+    /// 添加定时器,  tick_time指定某一时间添加触发定时器, 该时间为单调时钟的时间, 参见now_monotonic
+    pub fn add_new_timer_at_monotonic(
+        &mut self,
+        tick_time: u64,
+        timer_cb: Option<TimerCb>,
+        data: Option<Box<dyn Any>>,
+    ) -> io::Result<TimerId> {
+        self.timer.add_first_timer(EventEntry::new_timer_at_monotonic(
+            tick_time,
+            timer_cb,
+            data,
+        ))
+    }
+
+    /// 删除指定的定时器id, 已失效的句柄返回None, 红黑树实现下删除定时器的时间为O(logn), 时间轮实现下为O(1), 如果存在该定时器, 则返回相关的定时器信息
+    pub fn del_timer(&mut self, time_id: TimerId) -> Option<EventEntry> {
         self.timer.del_timer(time_id)
     }
 
+    /// 重新设置定时器的间隔为tick_step微秒, 并从当前时间起重新计时, 定时器id保持不变
+    /// 正在执行回调的定时器不在定时器队列中, 修改其间隔应通过回调返回RetValue::CONTINUE
+    pub fn reset_timer(&mut self, time_id: TimerId, tick_step: u64) -> bool {
+        self.timer.reset_timer(time_id, tick_step)
+    }
+
+    /// 把定时器的到期时间修改为tick_time, 该时间为单调时钟的时间, 定时器id保持不变
+    pub fn reschedule_timer_at(&mut self, time_id: TimerId, tick_time: u64) -> bool {
+        self.timer.reschedule_timer(time_id, tick_time)
+    }
+
+    /// 定时器距离到期还剩余的微秒数, 定时器不存在则返回None
+    pub fn timer_remaining(&self, time_id: TimerId) -> Option<u64> {
+        self.timer.timer_remaining(time_id)
+    }
+
+    /// 判断定时器是否存在
+    pub fn has_timer(&self, time_id: TimerId) -> bool {
+        self.timer.has_timer(time_id)
+    }
+
     /// 添加socket监听
     pub fn register_socket(&mut self, buffer: EventBuffer, entry: EventEntry) -> io::Result<()> {
         let _ = Selector::register_socket(self, buffer, entry)?;
         Ok(())
     }
 
-    /// 修改socket监听
+    /// 修改socket监听, is_del为true时移除entry中的事件, 否则加入entry中的事件
+    /// 非持久的读写事件回调一次后即停止监听, 可通过该接口重新加入FLAG_READ或FLAG_WRITE
     pub fn modify_socket(&mut self, is_del: bool, socket: SOCKET, entry: EventEntry) -> io::Result<()> {
         let _ = Selector::modify_socket(self, is_del, socket, entry)?;
         Ok(())
     }
 
 
+    /// 关闭socket的写端, 写缓冲中的数据发送完毕后向对端发送FIN, 读端不受影响
+    /// 对端也已半关闭(见EventEntry::with_read_eof)时结束该socket
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn shutdown_write(&mut self, socket: SOCKET) -> io::Result<()> {
+        Selector::shutdown_write(self, socket)
+    }
+
+    /// 停止读取socket, 写缓冲中的数据发送完毕后关闭该socket并调用结束回调, 结束原因为EndReason::Flushed
+    /// timeout为等待发送的最长时间, 单位为微秒, 为0表示不限制, 超时后以TimedOut错误关闭
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn close_after_flush(&mut self, socket: SOCKET, timeout: u64) -> io::Result<()> {
+        Selector::close_after_flush(self, socket, timeout)
+    }
+
+    /// 暂停读取socket, 不再监听可读, 缓冲, 回调及数据保持不变, 读空闲超时同时暂停
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn pause_read(&mut self, socket: SOCKET) -> io::Result<()> {
+        Selector::pause_read(self, socket)
+    }
+
+    /// 恢复被pause_read暂停的读取, 暂停期间到达的数据在恢复后回调, 读缓冲中已有的数据在恢复时立即回调
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn resume_read(&mut self, socket: SOCKET) -> io::Result<()> {
+        Selector::resume_read(self, socket)
+    }
+
     /// 删除指定socket的句柄信息
     pub fn unregister_socket(&mut self, ev_fd: SOCKET) -> io::Result<()> {
         let _ = Selector::unregister_socket(self, ev_fd)?;
@@ -169,20 +352,57 @@ impl EventLoop {
     }
 
     /// 添加定时器, ev_fd为socket的句柄id, ev_events为监听读, 写, 持久的信息
-    pub fn add_new_event(
+    pub fn add_new_event<S: Into<StreamSocket>>(
         &mut self,
-        socket: TcpSocket,
+        socket: S,
         ev_events: EventFlags,
         read: Option<EventCb>,
         write: Option<EventCb>,
         error: Option<EndCb>,
         data: Option<Box<dyn Any>>,
     ) -> io::Result<()> {
-        let ev_fd = socket.as_raw_socket();
         let buffer = self.new_buff(socket);
+        let ev_fd = buffer.as_raw_socket();
         self.register_socket(buffer, EventEntry::new_event(ev_fd, ev_events, read, write, error, data))
     }
 
+    /// 异步连接addr, timeout为连接的超时微秒数, 为0表示不限制, 返回该socket的句柄id
+    /// 连接成功后回调connected并转为正常的读写事件, 连接失败或超时也回调connected
+    /// 此时EventBuffer.error中为失败的原因, 之后关闭该socket并回调end
+    pub fn connect(
+        &mut self,
+        addr: &SocketAddr,
+        timeout: u64,
+        connected: Option<EventCb>,
+        read: Option<EventCb>,
+        end: Option<EndCb>,
+        data: Option<Box<dyn Any>>,
+    ) -> io::Result<SOCKET> {
+        let socket = TcpSocket::connect_asyn(addr)?;
+        socket.set_nonblocking(true)?;
+        let ev_fd = socket.as_raw_socket();
+        let buffer = self.new_buff(socket);
+        self.register_socket(buffer, EventEntry::new_connect(ev_fd, timeout, connected, read, end, data))?;
+        Ok(ev_fd)
+    }
+
+    /// 以闭包作为回调的异步连接, 参数含义同connect
+    pub fn connect_fn(
+        &mut self,
+        addr: &SocketAddr,
+        timeout: u64,
+        connected: Option<EventFn>,
+        read: Option<EventFn>,
+        end: Option<EndFn>,
+    ) -> io::Result<SOCKET> {
+        let socket = TcpSocket::connect_asyn(addr)?;
+        socket.set_nonblocking(true)?;
+        let ev_fd = socket.as_raw_socket();
+        let buffer = self.new_buff(socket);
+        self.register_socket(buffer, EventEntry::new_connect_fn(ev_fd, timeout, connected, read, end))?;
+        Ok(ev_fd)
+    }
+
     /// 添加定时器, ev_fd为socket的句柄id, ev_events为监听读, 写, 持久的信息
     pub fn add_new_accept(
         &mut self,
@@ -197,11 +417,440 @@ impl EventLoop {
         self.register_socket(buffer, EventEntry::new_accept(ev_fd, ev_events, accept, error, data))
     }
 
+    /// 添加闭包形式的socket事件, 参数含义同add_new_event, 状态由闭包自身捕获
+    pub fn add_new_event_fn<S: Into<StreamSocket>>(
+        &mut self,
+        socket: S,
+        ev_events: EventFlags,
+        read: Option<EventFn>,
+        write: Option<EventFn>,
+        error: Option<EndFn>,
+    ) -> io::Result<()> {
+        let buffer = self.new_buff(socket);
+        let ev_fd = buffer.as_raw_socket();
+        self.register_socket(buffer, EventEntry::new_event_fn(ev_fd, ev_events, read, write, error))
+    }
+
+    /// 添加闭包形式的监听事件, 参数含义同add_new_accept, 状态由闭包自身捕获
+    pub fn add_new_accept_fn(
+        &mut self,
+        socket: TcpSocket,
+        ev_events: EventFlags,
+        accept: Option<AcceptFn>,
+        error: Option<EndFn>,
+    ) -> io::Result<()> {
+        let ev_fd = socket.as_raw_socket();
+        let buffer = self.new_buff(socket);
+        self.register_socket(buffer, EventEntry::new_accept_fn(ev_fd, ev_events, accept, error))
+    }
+
+    /// 添加信号监听, signo为信号值, 如libc::SIGINT
+    /// 该信号在本线程被屏蔽, 由signalfd转为主循环中的回调, 回调返回RetValue::OVER则移除该监听
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn add_signal(
+        &mut self,
+        signo: i32,
+        signal: Option<SignalCb>,
+        data: Option<Box<dyn Any>>,
+    ) -> io::Result<()> {
+        Selector::register_signal(self, signo, EventEntry::new_signal(signal, data))
+    }
+
+    /// 添加闭包形式的信号监听, 参数含义同add_signal
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn add_signal_fn(&mut self, signo: i32, signal: SignalFn) -> io::Result<()> {
+        Selector::register_signal(self, signo, EventEntry::new_signal_fn(signal))
+    }
+
+    /// 删除信号监听, 并恢复该信号原先的屏蔽状态
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn del_signal(&mut self, signo: i32) -> io::Result<()> {
+        Selector::unregister_signal(self, signo)
+    }
+
+    /// 添加UDP socket监听, 每收到一个数据报回调一次recv_from, 回调返回RetValue::OVER则移除该监听
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn add_udp_socket(
+        &mut self,
+        socket: UdpSocket,
+        recv_from: Option<RecvFromCb>,
+        data: Option<Box<dyn Any>>,
+    ) -> io::Result<()> {
+        let ev_fd = socket.as_raw_socket();
+        Selector::register_datagram(self, socket.into(), EventEntry::new_udp(ev_fd, recv_from, data))
+    }
+
+    /// 添加闭包形式的UDP socket监听, 参数含义同add_udp_socket
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn add_udp_socket_fn(&mut self, socket: UdpSocket, recv_from: RecvFromFn) -> io::Result<()> {
+        let ev_fd = socket.as_raw_socket();
+        Selector::register_datagram(self, socket.into(), EventEntry::new_udp_fn(ev_fd, recv_from))
+    }
+
+    /// 以自定义的entry添加UDP socket监听, 如通过with_datagram_error设置出错时的回调
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn register_udp_socket(&mut self, socket: UdpSocket, entry: EventEntry) -> io::Result<()> {
+        Selector::register_datagram(self, socket.into(), entry)
+    }
+
+    /// 通过指定的UDP socket发送数据报, 内核缓冲已满时放入该socket的发送队列, 等待可写时按顺序发送
+    /// 返回值为当次发送的大小, 放入队列时返回0
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn send_to(&mut self, ev_fd: &SOCKET, data: &[u8], addr: &SocketAddr) -> io::Result<usize> {
+        Selector::send_to(self, ev_fd, data, &addr.clone().into())
+    }
+
+    /// 监听任意句柄, 如管道, eventfd, 终端, inotify等, 仅在就绪时回调ready并传入就绪的事件
+    /// 由调用者自行读写该句柄, 句柄也由调用者负责关闭, 回调返回RetValue::OVER则移除该监听
+    /// 若需要由主循环读写并缓存数据, 可以把句柄转为File后通过add_new_event注册
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn register_fd(
+        &mut self,
+        fd: SOCKET,
+        ev_events: EventFlags,
+        ready: Option<ReadyCb>,
+        data: Option<Box<dyn Any>>,
+    ) -> io::Result<()> {
+        Selector::register_fd(self, EventEntry::new_fd(fd, ev_events, ready, data))
+    }
+
+    /// 以闭包作为回调监听任意句柄, 参数含义同register_fd
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn register_fd_fn(&mut self, fd: SOCKET, ev_events: EventFlags, ready: ReadyFn) -> io::Result<()> {
+        Selector::register_fd(self, EventEntry::new_fd_fn(fd, ev_events, ready))
+    }
+
+    /// 修改句柄监听的事件
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn modify_fd(&mut self, fd: SOCKET, ev_events: EventFlags) -> io::Result<()> {
+        Selector::modify_fd(self, fd, ev_events)
+    }
+
+    /// 取消句柄的监听, 该句柄不会被关闭
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn unregister_fd(&mut self, fd: SOCKET) -> io::Result<()> {
+        Selector::unregister_fd(self, fd)
+    }
+
+    /// 添加Unix域socket的监听, 新连接以非阻塞的UnixStream回调accept
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn add_unix_listener(
+        &mut self,
+        listener: UnixListener,
+        accept: Option<UnixAcceptCb>,
+        end: Option<EndCb>,
+        data: Option<Box<dyn Any>>,
+    ) -> io::Result<()> {
+        listener.set_nonblocking(true)?;
+        let buffer = self.new_buff(listener);
+        let ev_fd = buffer.as_raw_socket();
+        let ev_events = EventFlags::FLAG_READ | EventFlags::FLAG_PERSIST | EventFlags::FLAG_ACCEPT;
+        self.register_socket(buffer, EventEntry::new_unix_accept(ev_fd, ev_events, accept, end, data))
+    }
+
+    /// 添加闭包形式的Unix域socket监听, 参数含义同add_unix_listener
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn add_unix_listener_fn(
+        &mut self,
+        listener: UnixListener,
+        accept: Option<UnixAcceptFn>,
+        end: Option<EndFn>,
+    ) -> io::Result<()> {
+        listener.set_nonblocking(true)?;
+        let buffer = self.new_buff(listener);
+        let ev_fd = buffer.as_raw_socket();
+        let ev_events = EventFlags::FLAG_READ | EventFlags::FLAG_PERSIST | EventFlags::FLAG_ACCEPT;
+        self.register_socket(buffer, EventEntry::new_unix_accept_fn(ev_fd, ev_events, accept, end))
+    }
+
+    /// 以非阻塞方式连接Unix域socket并加入监听, addr可以是文件路径或者抽象命名空间的地址, 返回该socket的句柄id
+    /// 本地连接通常立即完成, 对端的连接队列已满时不等待, 直接返回WouldBlock错误
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn connect_unix(
+        &mut self,
+        addr: &UnixSocketAddr,
+        read: Option<EventCb>,
+        end: Option<EndCb>,
+        data: Option<Box<dyn Any>>,
+    ) -> io::Result<SOCKET> {
+        let (stream, is_connecting) = connect_unix_asyn(addr)?;
+        let buffer = self.new_buff(stream);
+        let ev_fd = buffer.as_raw_socket();
+        let ev_events = EventFlags::FLAG_READ | EventFlags::FLAG_PERSIST;
+        let entry = if is_connecting {
+            EventEntry::new_connect(ev_fd, 0, None, read, end, data)
+        } else {
+            EventEntry::new_event(ev_fd, ev_events, read, None, end, data)
+        };
+        self.register_socket(buffer, entry)?;
+        Ok(ev_fd)
+    }
+
+    /// 以闭包作为回调连接Unix域socket, 参数含义同connect_unix
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn connect_unix_fn(
+        &mut self,
+        addr: &UnixSocketAddr,
+        read: Option<EventFn>,
+        end: Option<EndFn>,
+    ) -> io::Result<SOCKET> {
+        let (stream, is_connecting) = connect_unix_asyn(addr)?;
+        let buffer = self.new_buff(stream);
+        let ev_fd = buffer.as_raw_socket();
+        let ev_events = EventFlags::FLAG_READ | EventFlags::FLAG_PERSIST;
+        let entry = if is_connecting {
+            EventEntry::new_connect_fn(ev_fd, 0, None, read, end)
+        } else {
+            EventEntry::new_event_fn(ev_fd, ev_events, read, None, end)
+        };
+        self.register_socket(buffer, entry)?;
+        Ok(ev_fd)
+    }
+
+    /// 添加Unix域数据报socket的监听, 每收到一个数据报回调一次recv_from, 回调返回RetValue::OVER则移除该监听
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn add_unix_datagram(
+        &mut self,
+        socket: UnixDatagram,
+        recv_from: Option<UnixRecvFromCb>,
+        data: Option<Box<dyn Any>>,
+    ) -> io::Result<()> {
+        let ev_fd = socket.as_raw_fd();
+        Selector::register_datagram(self, socket.into(), EventEntry::new_unix_datagram(ev_fd, recv_from, data))
+    }
+
+    /// 以自定义的entry添加Unix域数据报socket监听, 同register_udp_socket
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn register_unix_datagram(&mut self, socket: UnixDatagram, entry: EventEntry) -> io::Result<()> {
+        Selector::register_datagram(self, socket.into(), entry)
+    }
+
+    /// 添加闭包形式的Unix域数据报socket监听, 参数含义同add_unix_datagram
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn add_unix_datagram_fn(&mut self, socket: UnixDatagram, recv_from: UnixRecvFromFn) -> io::Result<()> {
+        let ev_fd = socket.as_raw_fd();
+        Selector::register_datagram(self, socket.into(), EventEntry::new_unix_datagram_fn(ev_fd, recv_from))
+    }
+
+    /// 通过指定的Unix域数据报socket发送数据, 队列的处理同send_to
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn send_to_unix(&mut self, ev_fd: &SOCKET, data: &[u8], addr: &UnixSocketAddr) -> io::Result<usize> {
+        Selector::send_to(self, ev_fd, data, &addr.clone().into())
+    }
+
+    /// 取出数据报socket最近一次出错的原因, 出错时如设置了with_datagram_error也会回调通知
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn take_datagram_error(&mut self, ev_fd: SOCKET) -> Option<io::Error> {
+        Selector::take_datagram_error(self, ev_fd)
+    }
+
+    /// 删除UDP或Unix域数据报socket的监听并关闭该socket, 队列中未发送的数据报将被丢弃
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn del_udp_socket(&mut self, ev_fd: SOCKET) -> io::Result<()> {
+        Selector::unregister_datagram(self, ev_fd)
+    }
+
+    /// 启动子进程, 子进程的stdin, stdout, stderr均以管道连接到主循环
+    /// stdout, stderr的数据通过对应的读回调通知, 为None时丢弃该输出, 子进程退出时以退出状态回调exit
+    /// 通过send_socket(&child.stdin, ..)写入stdin, unregister_socket(child.stdin)关闭stdin
+    /// 优先以pidfd得知子进程退出, 内核不支持时改为监听SIGCHLD, 退出回调时管道中可能仍有未读完的数据
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn spawn(
+        &mut self,
+        mut command: Command,
+        stdout: Option<EventFn>,
+        stderr: Option<EventFn>,
+        exit: Option<ExitFn>,
+    ) -> io::Result<ChildProcess> {
+        command.stdin(Stdio::piped());
+        command.stdout(if stdout.is_some() { Stdio::piped() } else { Stdio::null() });
+        command.stderr(if stderr.is_some() { Stdio::piped() } else { Stdio::null() });
+        let mut child = command.spawn()?;
+        let pid = child.id();
+
+        // 任一步骤失败时结束并回收子进程, 已注册的管道也一并移除, 不留下无人管理的子进程
+        let process = match self.add_pipes(&mut child, stdout, stderr) {
+            Ok(process) => process,
+            Err(e) => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(e);
+            }
+        };
+        let ret = self.watch_process(pid).and_then(|pidfd| {
+            if pidfd.is_none() {
+                // 子进程可能在监听SIGCHLD前已经退出, 在下一次循环中检查一次
+                self.add_new_timer_fn(1, false, Box::new(|ev, _| {
+                    ev.reap_processes();
+                    (RetValue::OVER, 0)
+                }))?;
+            }
+            Ok(pidfd)
+        });
+        let pidfd = match ret {
+            Ok(pidfd) => pidfd,
+            Err(e) => {
+                self.del_pipes(&[Some(process.stdin), process.stdout, process.stderr]);
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(e);
+            }
+        };
+        self.processes.children.insert(pid, ProcessEntry {
+            child: child,
+            exit: exit,
+            pidfd: pidfd,
+            timer: TimerId::default(),
+        });
+        Ok(process)
+    }
+
+    /// 向spawn启动且尚未退出的子进程发送信号, 如libc::SIGTERM
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn kill_process(&mut self, pid: u32, signo: i32) -> io::Result<()> {
+        if !self.processes.children.contains_key(&pid) {
+            return Err(io::Error::new(io::ErrorKind::NotFound, "no such child process"));
+        }
+        if unsafe { libc::kill(pid as libc::pid_t, signo) } < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    /// 设置子进程的超时时间, 单位为微秒, 超时后以SIGKILL结束该子进程, 重复设置时以最后一次为准
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn set_process_timeout(&mut self, pid: u32, timeout: u64) -> io::Result<()> {
+        let old = match self.processes.children.get(&pid) {
+            Some(entry) => entry.timer,
+            None => return Err(io::Error::new(io::ErrorKind::NotFound, "no such child process")),
+        };
+        self.timer.del_timer(old);
+        let timer = self.add_timer(EventEntry::new_timer_fn(timeout, false, Box::new(move |ev, _| {
+            let _ = ev.kill_process(pid, libc::SIGKILL);
+            (RetValue::OVER, 0)
+        })))?;
+        if let Some(entry) = self.processes.children.get_mut(&pid) {
+            entry.timer = timer;
+        }
+        Ok(())
+    }
+
+    /// 把子进程的管道以非阻塞的方式加入监听
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    fn add_pipes(
+        &mut self,
+        child: &mut ::std::process::Child,
+        stdout: Option<EventFn>,
+        stderr: Option<EventFn>,
+    ) -> io::Result<ChildProcess> {
+        let stdin = match child.stdin.take() {
+            Some(stdin) => self.add_pipe(File::from(OwnedFd::from(stdin)), EventFlags::FLAG_PERSIST, None)?,
+            None => return Err(io::Error::new(io::ErrorKind::Other, "stdin is not piped")),
+        };
+        let stdout = match child.stdout.take() {
+            Some(out) => match self.add_pipe(File::from(OwnedFd::from(out)), EventFlags::FLAG_READ | EventFlags::FLAG_PERSIST, stdout) {
+                Ok(fd) => Some(fd),
+                Err(e) => {
+                    self.del_pipes(&[Some(stdin)]);
+                    return Err(e);
+                }
+            },
+            None => None,
+        };
+        let stderr = match child.stderr.take() {
+            Some(err) => match self.add_pipe(File::from(OwnedFd::from(err)), EventFlags::FLAG_READ | EventFlags::FLAG_PERSIST, stderr) {
+                Ok(fd) => Some(fd),
+                Err(e) => {
+                    self.del_pipes(&[Some(stdin), stdout]);
+                    return Err(e);
+                }
+            },
+            None => None,
+        };
+        Ok(ChildProcess {
+            pid: child.id(),
+            stdin: stdin,
+            stdout: stdout,
+            stderr: stderr,
+        })
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    fn add_pipe(&mut self, file: File, ev_events: EventFlags, read: Option<EventFn>) -> io::Result<SOCKET> {
+        let buffer = self.new_buff(file);
+        buffer.socket.set_nonblocking(true)?;
+        let ev_fd = buffer.as_raw_socket();
+        self.register_socket(buffer, EventEntry::new_event_fn(ev_fd, ev_events, read, None, None))?;
+        Ok(ev_fd)
+    }
+
+    /// 移除已注册的管道并关闭
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    fn del_pipes(&mut self, pipes: &[Option<SOCKET>]) {
+        for fd in pipes.iter().filter_map(|fd| *fd) {
+            let _ = self.unregister_socket(fd);
+        }
+    }
+
+    /// 监听子进程的退出, 返回pidfd, 内核不支持pidfd时监听SIGCHLD并返回None
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    fn watch_process(&mut self, pid: u32) -> io::Result<Option<RawFd>> {
+        let pidfd = unsafe { libc::syscall(libc::SYS_pidfd_open, pid as libc::pid_t, 0) } as RawFd;
+        if pidfd >= 0 {
+            let ready: ReadyFn = Box::new(move |ev, _, _| {
+                ev.reap_process(pid);
+                RetValue::OK
+            });
+            if let Err(e) = self.register_fd_fn(pidfd, EventFlags::FLAG_READ, ready) {
+                unsafe { libc::close(pidfd) };
+                return Err(e);
+            }
+            return Ok(Some(pidfd));
+        }
+        if !self.processes.sigchld {
+            self.add_signal_fn(libc::SIGCHLD, Box::new(|ev, _| {
+                ev.reap_processes();
+                RetValue::OK
+            }))?;
+            self.processes.sigchld = true;
+        }
+        Ok(None)
+    }
+
+    /// 检查子进程是否已退出, 已退出则回收该子进程并回调exit
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    fn reap_process(&mut self, pid: u32) {
+        let status = match self.processes.children.get_mut(&pid) {
+            Some(entry) => match entry.child.try_wait() {
+                Ok(Some(status)) => status,
+                _ => return,
+            },
+            None => return,
+        };
+        let mut entry = self.processes.children.remove(&pid).unwrap();
+        if let Some(pidfd) = entry.pidfd {
+            let _ = self.unregister_fd(pidfd);
+            unsafe { libc::close(pidfd) };
+        }
+        self.timer.del_timer(entry.timer);
+        if let Some(mut exit) = entry.exit.take() {
+            exit(self, pid, status);
+        }
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    fn reap_processes(&mut self) {
+        let pids: Vec<u32> = self.processes.children.keys().cloned().collect();
+        for pid in pids {
+            self.reap_process(pid);
+        }
+    }
+
     /// 定时器的处理处理
     /// 1.取出定时器的第一个, 如果第一个大于当前时间, 则跳出循环, 如果小于等于当前时间进入2
     /// 2.调用回调函数, 如果回调返回OVER或者定时器不是循环定时器, 则删除定时器, 否则把该定时器重时添加到列表
     fn timer_process(&mut self) -> bool {
-        let now = now_micro();
+        let now = now_monotonic();
         let mut is_op = false;
         loop {
             match self.timer.tick_time(now) {
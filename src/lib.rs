@@ -16,24 +16,37 @@ extern crate psocket;
 
 mod event_loop;
 mod timer;
+mod timer_wheel;
 mod event_flags;
 mod event_entry;
 mod event_buffer;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+mod event_handle;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+mod process;
 
-pub use timer::Timer;
+pub use timer::{Timer, TimerId, TimerBackend, TimerRepeat, MissedTick};
 pub use event_loop::{EventLoop, EventLoopConfig, RetValue};
 
-pub use event_buffer::{Buffer, EventBuffer};
+pub use event_buffer::{Buffer, EventBuffer, StreamSocket, EndReason};
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub use event_handle::{EventLoopHandle, Task};
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub use process::{ChildProcess, ExitFn};
 
 pub mod sys;
                       
 
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::time::{SystemTime, UNIX_EPOCH, Instant};
+use std::sync::OnceLock;
 pub use event_flags::{EventFlags};
 
 // pub use event_flags::{EventFlags, FLAG_TIMEOUT, FLAG_READ, FLAG_WRITE, FLAG_PERSIST, FLAG_ERROR,
 //     FLAG_ACCEPT, FLAG_ENDED, FLAG_READ_PERSIST, FLAG_WRITE_PERSIST};
-pub use event_entry::{EventEntry, AcceptCb, EventCb, TimerCb, EndCb, CellAny};
+pub use event_entry::{EventEntry, AcceptCb, EventCb, TimerCb, EndCb, SignalCb, RecvFromCb, ReadyCb, DatagramErrorCb, CellAny};
+pub use event_entry::{AcceptFn, EventFn, TimerFn, EndFn, SignalFn, RecvFromFn, ReadyFn, DatagramErrorFn};
+#[cfg(unix)]
+pub use event_entry::{UnixAcceptCb, UnixRecvFromCb, UnixAcceptFn, UnixRecvFromFn};
 pub use sys::{AsFd, FromFd};
 
 /// The macro convert Option<&mut Cell<Option<Box<Any>>>> to &mut ty
@@ -65,11 +78,29 @@ macro_rules! any_unwrap {
     };
 }
 
+/// 当前的墙上时间, 自UNIX_EPOCH起的微秒数, 会受系统时间调整的影响, 仅用于按日历时间调度
 pub fn now_micro() -> u64 {
     let start = SystemTime::now();
     let since_the_epoch = start
         .duration_since(UNIX_EPOCH)
         .expect("Time went backwards");
-    let ms = since_the_epoch.as_secs() as u64 * 1000u64 + (since_the_epoch.subsec_nanos() as f64 / 1_000_000.0) as u64;
-    ms
+    since_the_epoch.as_secs() as u64 * 1_000_000u64 + since_the_epoch.subsec_micros() as u64
+}
+
+/// 单调时钟, 自进程首次调用起的微秒数, 不受系统时间调整的影响, 定时器的时间均以此为准
+pub fn now_monotonic() -> u64 {
+    static START: OnceLock<Instant> = OnceLock::new();
+    let elapsed = START.get_or_init(Instant::now).elapsed();
+    elapsed.as_secs() * 1_000_000u64 + elapsed.subsec_micros() as u64
+}
+
+/// 把墙上时间(now_micro的时间)转换为单调时钟的时间, 早于当前时间的则视为立即到期
+pub fn wall_to_monotonic(wall_time: u64) -> u64 {
+    let now_wall = now_micro();
+    let now_mono = now_monotonic();
+    if wall_time > now_wall {
+        now_mono + (wall_time - now_wall)
+    } else {
+        now_mono
+    }
 }
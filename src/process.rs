@@ -0,0 +1,40 @@
+use std::collections::HashMap;
+use std::os::unix::io::RawFd;
+use std::process::{Child, ExitStatus};
+use psocket::SOCKET;
+use {EventLoop, TimerId};
+
+/// 子进程退出的回调, 参数为子进程的pid及退出状态
+pub type ExitFn = Box<dyn FnMut(&mut EventLoop, u32, ExitStatus)>;
+
+/// 由EventLoop::spawn启动的子进程, stdin, stdout, stderr为管道在主循环中的句柄id
+#[derive(Copy, Clone, Debug)]
+pub struct ChildProcess {
+    pub pid: u32,
+    pub stdin: SOCKET,
+    pub stdout: Option<SOCKET>,
+    pub stderr: Option<SOCKET>,
+}
+
+/// 主循环中等待退出的子进程, pidfd为None时表示通过SIGCHLD得知退出
+pub struct ProcessEntry {
+    pub child: Child,
+    pub exit: Option<ExitFn>,
+    pub pidfd: Option<RawFd>,
+    pub timer: TimerId,
+}
+
+pub struct Processes {
+    pub children: HashMap<u32, ProcessEntry>,
+    /// 是否已监听SIGCHLD
+    pub sigchld: bool,
+}
+
+impl Processes {
+    pub fn new() -> Processes {
+        Processes {
+            children: HashMap::new(),
+            sigchld: false,
+        }
+    }
+}
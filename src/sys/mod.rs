@@ -18,6 +18,9 @@ mod unix;
 #[cfg(not(windows))]
 pub use self::unix::Selector;
 
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub use self::unix::{Awakener, connect_unix_asyn};
+
 #[doc(hidden)]
 pub trait AsFd {
     fn as_fd(&self) -> i32;
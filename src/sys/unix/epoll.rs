@@ -1,13 +1,22 @@
 #![allow(dead_code)]
-use std::os::unix::io::RawFd;
+use std::os::unix::io::{RawFd, AsRawFd};
 use std::io::{self, ErrorKind};
-use {EventEntry, EventFlags, FLAG_READ, FLAG_WRITE, FLAG_ACCEPT, EventBuffer, EventLoop, RetValue};
+use std::cmp;
+use {EventEntry, EventFlags, FLAG_READ, FLAG_WRITE, FLAG_ACCEPT, FLAG_CONNECT, FLAG_EDGE, FLAG_PERSIST, FLAG_READ_PERSIST, FLAG_WRITE_PERSIST, FLAG_TIMEOUT, FLAG_ERROR, FLAG_ENDED, EventBuffer, StreamSocket, EndReason, EventLoop, RetValue, TimerId};
 
-use std::collections::HashMap;
-use psocket::SOCKET;
+use std::collections::{HashMap, VecDeque};
+use std::convert::TryFrom;
+use std::sync::Arc;
+use psocket::{SOCKET, SocketAddr, UdpSocket};
+use std::os::unix::net::{UnixDatagram, SocketAddr as UnixSocketAddr};
 
-use nix::unistd::close;
+use nix::unistd::{close, read, write};
 use nix::sys::epoll::*;
+use nix::sys::eventfd::{eventfd, EfdFlags};
+use nix::sys::signal::{SigSet, Signal};
+use nix::sys::signalfd::{SignalFd, SfdFlags};
+use nix::sys::time::{TimeSpec, TimeValLike};
+use nix::sys::timerfd::{TimerFd, ClockId, TimerFlags, TimerSetTimeFlags, Expiration};
 use std::io::prelude::*;
 
 use super::FromRawArc;
@@ -16,12 +25,178 @@ pub struct Selector {
     epfd: RawFd,
     evts: Events,
     event_maps: HashMap<SOCKET, EventImpl>,
+    signal_maps: HashMap<RawFd, FromRawArc<SignalEvent>>,
+    datagram_maps: HashMap<SOCKET, FromRawArc<DatagramEvent>>,
+    fd_maps: HashMap<RawFd, FromRawArc<FdEvent>>,
+    awakener: Arc<Awakener>,
+    timer_fd: TimerFd,
+}
+
+/// 信号事件, 每个信号对应一个signalfd, 注册时在本线程屏蔽该信号, 移除时恢复原先的屏蔽状态
+pub struct SignalEvent {
+    pub signo: i32,
+    pub sfd: SignalFd,
+    pub entry: EventEntry,
+    pub was_blocked: bool,
+}
+
+/// 数据报socket, UDP或者Unix域数据报
+pub enum DatagramSocket {
+    Udp(UdpSocket),
+    Unix(UnixDatagram),
+}
+
+/// 数据报的对端地址
+#[derive(Clone)]
+pub enum DatagramAddr {
+    Inet(SocketAddr),
+    Unix(UnixSocketAddr),
+}
+
+impl DatagramSocket {
+    pub fn as_raw_fd(&self) -> RawFd {
+        match *self {
+            DatagramSocket::Udp(ref socket) => socket.as_raw_socket(),
+            DatagramSocket::Unix(ref socket) => socket.as_raw_fd(),
+        }
+    }
+
+    pub fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()> {
+        match *self {
+            DatagramSocket::Udp(ref socket) => socket.set_nonblocking(nonblocking),
+            DatagramSocket::Unix(ref socket) => socket.set_nonblocking(nonblocking),
+        }
+    }
+
+    pub fn take_error(&self) -> io::Result<Option<io::Error>> {
+        match *self {
+            DatagramSocket::Udp(ref socket) => socket.take_error(),
+            DatagramSocket::Unix(ref socket) => socket.take_error(),
+        }
+    }
+
+    pub fn recv_from(&self, buf: &mut [u8]) -> io::Result<(usize, DatagramAddr)> {
+        match *self {
+            DatagramSocket::Udp(ref socket) => {
+                socket.recv_from(buf).map(|(len, addr)| (len, DatagramAddr::Inet(addr)))
+            }
+            DatagramSocket::Unix(ref socket) => {
+                socket.recv_from(buf).map(|(len, addr)| (len, DatagramAddr::Unix(addr)))
+            }
+        }
+    }
+
+    pub fn send_to(&self, buf: &[u8], addr: &DatagramAddr) -> io::Result<usize> {
+        match (self, addr) {
+            (&DatagramSocket::Udp(ref socket), &DatagramAddr::Inet(ref addr)) => socket.send_to(buf, addr),
+            (&DatagramSocket::Unix(ref socket), &DatagramAddr::Unix(ref addr)) => socket.send_to_addr(buf, addr),
+            _ => Err(io::Error::new(ErrorKind::InvalidInput, "address family mismatch")),
+        }
+    }
+}
+
+impl From<UdpSocket> for DatagramSocket {
+    fn from(socket: UdpSocket) -> DatagramSocket {
+        DatagramSocket::Udp(socket)
+    }
+}
+
+impl From<UnixDatagram> for DatagramSocket {
+    fn from(socket: UnixDatagram) -> DatagramSocket {
+        DatagramSocket::Unix(socket)
+    }
+}
+
+impl From<SocketAddr> for DatagramAddr {
+    fn from(addr: SocketAddr) -> DatagramAddr {
+        DatagramAddr::Inet(addr)
+    }
+}
+
+impl From<UnixSocketAddr> for DatagramAddr {
+    fn from(addr: UnixSocketAddr) -> DatagramAddr {
+        DatagramAddr::Unix(addr)
+    }
+}
+
+/// 数据报事件, 内核缓冲已满时待发送的数据报按顺序存放在队列中, 等待可写时继续发送
+pub struct DatagramEvent {
+    pub socket: DatagramSocket,
+    pub entry: EventEntry,
+    pub queue: VecDeque<(Vec<u8>, DatagramAddr)>,
+    pub read_cache: Vec<u8>,
+    /// 最近一次收发出错的原因, 由EventLoop::take_datagram_error取出
+    pub last_error: Option<io::Error>,
+}
+
+/// 任意句柄的就绪事件, 由调用者自行读写
+pub struct FdEvent {
+    pub fd: RawFd,
+    pub entry: EventEntry,
+}
+
+/// 基于eventfd的唤醒器, 其它线程可通过它唤醒阻塞在epoll_wait中的主循环
+pub struct Awakener {
+    fd: RawFd,
+}
+
+impl Awakener {
+    pub fn new() -> io::Result<Awakener> {
+        let fd = eventfd(0, EfdFlags::EFD_CLOEXEC | EfdFlags::EFD_NONBLOCK)
+            .map_err(super::from_nix_error)?;
+        Ok(Awakener { fd: fd })
+    }
+
+    pub fn as_raw_fd(&self) -> RawFd {
+        self.fd
+    }
+
+    /// 唤醒主循环, 计数器已满时返回EAGAIN, 此时主循环必然会被唤醒, 因此忽略该错误
+    pub fn wakeup(&self) -> io::Result<()> {
+        match write(self.fd, &1u64.to_ne_bytes()).map_err(super::from_nix_error) {
+            Err(ref e) if e.kind() == ErrorKind::WouldBlock => Ok(()),
+            Err(e) => Err(e),
+            Ok(_) => Ok(()),
+        }
+    }
+
+    /// 清空计数器, 以便下次唤醒
+    pub fn reset(&self) {
+        let mut buf = [0u8; 8];
+        let _ = read(self.fd, &mut buf);
+    }
+}
+
+impl Drop for Awakener {
+    fn drop(&mut self) {
+        let _ = close(self.fd);
+    }
 }
 
 pub struct Event {
     pub buffer: EventBuffer,
     pub entry: EventEntry,
     pub is_end: bool,
+    /// 读空闲, 写阻塞及存活时间的内部定时器
+    pub read_timer: TimerId,
+    pub write_timer: TimerId,
+    pub life_timer: TimerId,
+    pub connect_timer: TimerId,
+    pub flush_timer: TimerId,
+    /// 是否在可写时回调write, 注册时带有FLAG_WRITE则为true, 非持久的写事件回调一次后置为false
+    pub is_write_notify: bool,
+    /// 对端已半关闭, 不再读取
+    pub is_read_eof: bool,
+    /// 已请求关闭写端, 等待写缓冲清空后发送FIN
+    pub is_shutdown_pending: bool,
+    /// 写端已关闭
+    pub is_write_closed: bool,
+    /// 等待写缓冲清空后关闭socket
+    pub is_closing: bool,
+    /// 写缓冲已达到高水位, 降到低水位前send_socket返回WouldBlock
+    pub is_write_blocked: bool,
+    /// 已暂停读取, 恢复前不监听可读
+    pub is_read_paused: bool,
 }
 
 #[derive(Clone)]
@@ -38,9 +213,21 @@ impl EventImpl {
 impl Event {
     pub fn new(buffer: EventBuffer, entry: EventEntry) -> Event {
         Event {
+            is_end: false,
+            read_timer: TimerId::default(),
+            write_timer: TimerId::default(),
+            life_timer: TimerId::default(),
+            connect_timer: TimerId::default(),
+            flush_timer: TimerId::default(),
+            is_write_notify: entry.ev_events.contains(FLAG_WRITE),
+            is_read_eof: false,
+            is_shutdown_pending: false,
+            is_write_closed: false,
+            is_closing: false,
+            is_write_blocked: false,
+            is_read_paused: false,
             buffer: buffer,
             entry: entry,
-            is_end: false,
         }
     }
 
@@ -48,9 +235,143 @@ impl Event {
         self.entry.ev_events.contains(FLAG_ACCEPT)
     }
 
+    pub fn is_connecting(&self) -> bool {
+        self.entry.ev_events.contains(FLAG_CONNECT)
+    }
+
+    pub fn is_edge(&self) -> bool {
+        self.entry.ev_events.contains(FLAG_EDGE)
+    }
+
+    pub fn is_read_persist(&self) -> bool {
+        self.entry.ev_events.intersects(FLAG_PERSIST | FLAG_READ_PERSIST)
+    }
+
+    pub fn is_write_persist(&self) -> bool {
+        self.entry.ev_events.intersects(FLAG_PERSIST | FLAG_WRITE_PERSIST)
+    }
+
     pub fn as_raw_socket(&self) -> SOCKET {
         self.buffer.socket.as_raw_socket()
     }
+
+    /// 读缓冲是否已达到上限
+    fn is_read_full(&self) -> bool {
+        self.entry.read_max > 0 && self.buffer.read.len() >= self.entry.read_max
+    }
+
+    /// 读缓冲中的数据是否达到低水位, 低水位不超过读缓冲的上限
+    fn is_read_enough(&self) -> bool {
+        let low = if self.entry.read_max > 0 {
+            cmp::min(self.entry.read_low, self.entry.read_max)
+        } else {
+            self.entry.read_low
+        };
+        self.buffer.has_read_buffer() && self.buffer.read.len() >= low
+    }
+
+    /// 写缓冲达到高水位时标记为阻塞, 之后的send_socket返回WouldBlock
+    fn check_write_high(&mut self) {
+        if self.entry.write_high > 0 && self.buffer.write.len() >= self.entry.write_high {
+            self.is_write_blocked = true;
+        }
+    }
+
+    /// 注册时添加读空闲, 存活时间及连接超时的定时器
+    fn add_timers(&mut self, event_loop: &mut EventLoop, socket: SOCKET) -> io::Result<()> {
+        self.read_timer = add_socket_timer(event_loop, socket, self.entry.read_timeout, "read idle timeout")?;
+        self.life_timer = add_socket_timer(event_loop, socket, self.entry.life_timeout, "lifetime timeout")?;
+        if self.is_connecting() {
+            self.connect_timer = add_socket_timer(event_loop, socket, self.entry.connect_timeout, "connect timeout")?;
+        }
+        Ok(())
+    }
+
+    /// 判断time_id是否为该socket当前的超时定时器
+    fn has_timer(&self, time_id: TimerId) -> bool {
+        [self.read_timer, self.write_timer, self.life_timer, self.connect_timer, self.flush_timer]
+            .iter()
+            .any(|id| !id.is_none() && *id == time_id)
+    }
+
+    /// 移除该socket所有的超时定时器
+    fn del_timers(&mut self, event_loop: &mut EventLoop) {
+        for time_id in &mut [&mut self.read_timer, &mut self.write_timer, &mut self.life_timer, &mut self.connect_timer, &mut self.flush_timer] {
+            if !time_id.is_none() {
+                event_loop.del_timer(**time_id);
+                **time_id = TimerId::default();
+            }
+        }
+    }
+}
+
+/// 为socket添加超时定时器, 到期时以TimedOut错误关闭该socket, timeout为0时不添加
+fn add_socket_timer(
+    event_loop: &mut EventLoop,
+    socket: SOCKET,
+    timeout: u64,
+    reason: &'static str,
+) -> io::Result<TimerId> {
+    if timeout == 0 {
+        return Ok(TimerId::default());
+    }
+    let timer = EventEntry::new_timer_fn(timeout, false, Box::new(move |ev, time_id| {
+        Selector::timeout_socket(ev, socket, time_id, reason);
+        (RetValue::OVER, 0)
+    }));
+    event_loop.add_timer(timer)
+}
+
+/// 异步连接的socket可写或出错, 根据SO_ERROR判断连接结果, err不为空时表示连接已失败
+fn connect_done(event_loop: &mut EventLoop, socket: SOCKET, err: Option<io::Error>) {
+    let mut event = match event_loop.selector.event_maps.get(&socket) {
+        Some(event) => event.clone(),
+        None => return,
+    };
+    let event_clone = &mut (*event.clone().inner);
+    let event = &mut (*event.inner);
+    let err = match err {
+        Some(err) => Some(err),
+        None => match event.buffer.socket.take_error() {
+            Ok(err) => err,
+            Err(err) => Some(err),
+        },
+    };
+    if !event.connect_timer.is_none() {
+        event_loop.del_timer(event.connect_timer);
+        event.connect_timer = TimerId::default();
+    }
+
+    if let Some(err) = err {
+        event.buffer.set_error(err);
+        event.buffer.set_end_reason(EndReason::ConnectFailed);
+        let _ = event.entry.connect_cb(event_loop, &mut event_clone.buffer);
+        let _ = Selector::unregister_socket(event_loop, socket);
+        return;
+    }
+
+    // 连接成功, 转为正常的读写监听, 连接期间写入的数据由写事件继续发送
+    if let Some(socket) = event.buffer.socket.as_tcp() {
+        socket.set_ready(true);
+    }
+    event.entry.ev_events.remove(FLAG_CONNECT);
+    if let Err(err) = event_loop.selector.modregister(socket, event.entry.ev_events) {
+        event.buffer.set_error(err);
+        event.buffer.set_end_reason(EndReason::ModifyFailed);
+        let _ = event.entry.connect_cb(event_loop, &mut event_clone.buffer);
+        let _ = Selector::unregister_socket(event_loop, socket);
+        return;
+    }
+    match event.entry.connect_cb(event_loop, &mut event_clone.buffer) {
+        RetValue::OVER => {
+            event.buffer.set_end_reason(EndReason::Over);
+            let _ = Selector::unregister_socket(event_loop, socket);
+        }
+        RetValue::FLUSH => {
+            let _ = Selector::close_after_flush(event_loop, socket, 0);
+        }
+        _ => (),
+    }
 }
 
 fn read_done(event_loop: &mut EventLoop, socket: SOCKET) {
@@ -58,122 +379,667 @@ fn read_done(event_loop: &mut EventLoop, socket: SOCKET) {
         return;
     }
     let mut event = event_loop.selector.event_maps.get_mut(&socket).map(|e| e.clone()).unwrap();
-    let event_clone = &mut (*event.clone().inner);
     let event = &mut (*event.inner);
+    if event.is_connecting() {
+        connect_done(event_loop, socket, None);
+        return;
+    }
+    // 同一批次中已就绪的事件可能在前面的回调中被暂停
+    if event.is_read_paused {
+        return;
+    }
+    // 边缘触发模式下必须读到EAGAIN为止, 否则剩余的数据或连接不会再次通知
+    let is_edge = event.is_edge();
     if event.is_accept() {
-        let ret = match event.buffer.socket.accept() {
-            Ok((mut socket, addr)) => {
-                socket.set_peer_addr(addr);
-                event.entry.accept_cb(event_loop, Ok(socket))
-            },
-            Err(e) => {
-                event.entry.accept_cb(event_loop, Err(e))
-            }
-        };
+        loop {
+            // 边缘触发模式下只在成功接受连接后继续, 其它错误(如EMFILE)回调后即退出, 避免一直重试
+            let mut is_accepted = true;
+            let ret = match event.buffer.socket {
+                StreamSocket::UnixListener(ref listener) => {
+                    match listener.accept() {
+                        Ok((stream, _)) => {
+                            let ret = stream.set_nonblocking(true).map(|_| stream);
+                            event.entry.accept_unix_cb(event_loop, ret)
+                        },
+                        Err(ref e) if is_edge && e.kind() == ErrorKind::WouldBlock => break,
+                        Err(e) => {
+                            is_accepted = false;
+                            event.entry.accept_unix_cb(event_loop, Err(e))
+                        }
+                    }
+                },
+                _ => {
+                    match event.buffer.socket.accept() {
+                        Ok((mut socket, addr)) => {
+                            socket.set_peer_addr(addr);
+                            event.entry.accept_cb(event_loop, Ok(socket))
+                        },
+                        Err(ref e) if is_edge && e.kind() == ErrorKind::WouldBlock => break,
+                        Err(e) => {
+                            is_accepted = false;
+                            event.entry.accept_cb(event_loop, Err(e))
+                        }
+                    }
+                },
+            };
 
-        match ret {
-            RetValue::OVER => {
-                let _ = event_loop.unregister_socket(event.as_raw_socket());
+            match ret {
+                RetValue::OVER => {
+                    event.buffer.set_end_reason(EndReason::Over);
+                    let _ = event_loop.unregister_socket(event.as_raw_socket());
+                    return;
+                }
+                _ => {
+                    ;
+                }
+            }
+            if !event_loop.selector.event_maps.contains_key(&socket) {
+                return;
+            }
+            if !event.is_read_persist() {
+                stop_read(event_loop, event);
+                return;
             }
-            _ => {
-                ;
+            if !is_edge || !is_accepted {
+                return;
             }
         }
     } else {
-        match event.buffer.socket.read(&mut event.buffer.read_cache[..]) {
+        // 边缘触发模式下因读缓冲达到上限而停止读取, 读回调取走数据后继续读取
+        loop {
+            let mut is_read = false;
+            let mut is_end = false;
+            let mut is_full = false;
+            loop {
+                match event.buffer.socket.read(&mut event.buffer.read_cache[..]) {
+                    Ok(len) => {
+                        if len <= 0 {
+                            is_end = true;
+                            break;
+                        }
+
+                        let _ = event.buffer.read.write(
+                            &event.buffer.read_cache
+                                [..len],
+                        );
+                        is_read = true;
+                        if !is_edge {
+                            break;
+                        }
+                        if event.is_read_full() {
+                            is_full = true;
+                            break;
+                        }
+                    },
+                    Err(ref err) if is_edge && err.kind() == ErrorKind::WouldBlock => break,
+                    Err(err) => {
+                        event.buffer.set_error(err);
+                        is_end = true;
+                        break;
+                    },
+                };
+            }
+
+            if is_read {
+                if !event.read_timer.is_none() {
+                    event_loop.reset_timer(event.read_timer, event.entry.read_timeout);
+                }
+
+                if !read_notify(event_loop, socket, is_end) {
+                    return;
+                }
+            }
+
+            // 已读取的数据先交给读回调, 再结束该socket
+            if is_end && event.buffer.error.is_ok() && event.entry.has_read_eof()
+                && event_loop.selector.event_maps.contains_key(&socket) {
+                read_eof(event_loop, socket);
+                return;
+            }
+            if is_end && event_loop.selector.event_maps.contains_key(&socket) {
+                if event.buffer.error.is_ok() {
+                    event.buffer.end_flags.insert(FLAG_ENDED);
+                    event.buffer.set_end_reason(EndReason::Eof);
+                } else {
+                    event.buffer.set_end_reason(EndReason::ReadError);
+                }
+                let _ = Selector::unregister_socket(
+                    event_loop,
+                    event.buffer.as_raw_socket(),
+                );
+                return;
+            }
+
+            if !is_full || !event_loop.selector.event_maps.contains_key(&socket)
+                || event.is_read_paused || !event.entry.ev_events.contains(FLAG_READ) {
+                return;
+            }
+        }
+    }
+}
+
+fn write_done(event_loop: &mut EventLoop, socket: SOCKET) {
+    if !event_loop.selector.event_maps.contains_key(&socket) {
+        return;
+    }
+    let mut event = event_loop.selector.event_maps.get_mut(&socket).map(|e| e.clone()).unwrap();
+    let event = &mut (*event.inner);
+    if event.is_connecting() {
+        connect_done(event_loop, socket, None);
+        return;
+    }
+    if event.is_edge() {
+        if let Err(err) = edge_write(event_loop, event) {
+            event.buffer.set_error(err);
+            event.buffer.set_end_reason(EndReason::WriteError);
+            let _ = Selector::unregister_socket(event_loop, socket);
+            return;
+        }
+        if !writable_notify(event_loop, socket) {
+            return;
+        }
+        if event.buffer.write.empty() {
+            if event.is_closing {
+                event.buffer.set_end_reason(EndReason::Flushed);
+                let _ = Selector::unregister_socket(event_loop, socket);
+            } else if event.is_shutdown_pending {
+                close_write(event_loop, socket);
+            } else {
+                let _ = write_notify(event_loop, socket);
+            }
+        }
+        return;
+    }
+    if !event.buffer.write.empty() {
+        match event.buffer.socket.write(&event.buffer.write.get_data()[..]) {
             Ok(len) => {
                 if len <= 0 {
+                    event.buffer.set_error(io::Error::new(ErrorKind::WriteZero, "failed to write the buffer"));
+                    event.buffer.set_end_reason(EndReason::WriteError);
                     let _ = Selector::unregister_socket(
                         event_loop,
-                        event.buffer.as_raw_socket(),
+                        event.buffer.as_raw_socket()
                     );
                     return;
                 }
-
-                let _ = event.buffer.read.write(
-                    &event.buffer.read_cache
-                        [..len],
-                );
-
-                if event.buffer.has_read_buffer() {
-                    match event.entry.read_cb(event_loop, &mut event_clone.buffer) {
-                        RetValue::OVER => {
-                            let _ = event_loop.unregister_socket(event.as_raw_socket());
-                            return;
-                        }
-                        _ => (),
+                event.buffer.write.drain(len);
+                if !event.buffer.write.empty() {
+                    // 有写出进度, 重新计算写阻塞超时
+                    if !event.write_timer.is_none() {
+                        event_loop.reset_timer(event.write_timer, event.entry.write_timeout);
                     }
+                    let _ = writable_notify(event_loop, socket);
+                    return;
                 }
             },
             Err(err) => {
-                event.buffer.error = Err(err);
+                event.buffer.set_error(err);
+                event.buffer.set_end_reason(EndReason::WriteError);
                 let _ = Selector::unregister_socket(
                     event_loop,
                     event.buffer.as_raw_socket()
                 );
+                return;
             },
-        };
+        }
+    }
+
+    // 写入包为空, 则表示没有数据要进行写入
+    event.buffer.is_in_write = false;
+    if !event.write_timer.is_none() {
+        event_loop.del_timer(event.write_timer);
+        event.write_timer = TimerId::default();
+    }
+    // 回调中可能又写入了数据, 等待下一次可写通知
+    if !writable_notify(event_loop, socket) || event.buffer.is_in_write {
+        return;
+    }
+    if event.is_closing {
+        event.buffer.set_end_reason(EndReason::Flushed);
+        let _ = Selector::unregister_socket(event_loop, socket);
+        return;
+    }
+    if event.is_shutdown_pending {
+        close_write(event_loop, socket);
+        return;
+    }
+    if !write_notify(event_loop, socket) {
+        return;
+    }
+    // 回调中没有再写入数据, 且不再需要可写通知, 则取消掉写入事件
+    if !event.buffer.is_in_write && !event.is_write_notify {
+        event.entry.ev_events.remove(FLAG_WRITE);
+        let _ = event_loop.selector.modregister(event.as_raw_socket(), event.entry.ev_events);
     }
 }
 
-fn write_done(event_loop: &mut EventLoop, socket: SOCKET) {
+/// 读缓冲中的数据达到低水位时回调read, 回调之后读缓冲仍达到上限则暂停读取或关闭该socket
+/// is_end为本次已读到EOF或出错, 返回false表示该socket已关闭或已暂停读取
+fn read_notify(event_loop: &mut EventLoop, socket: SOCKET, is_end: bool) -> bool {
+    let mut event = match event_loop.selector.event_maps.get(&socket) {
+        Some(event) => event.clone(),
+        None => return false,
+    };
+    let event_clone = &mut (*event.clone().inner);
+    let event = &mut (*event.inner);
+    if event.is_read_paused {
+        return false;
+    }
+    if event.is_read_enough() {
+        match event.entry.read_cb(event_loop, &mut event_clone.buffer) {
+            RetValue::OVER => {
+                event.buffer.set_end_reason(EndReason::Over);
+                let _ = Selector::unregister_socket(event_loop, socket);
+                return false;
+            }
+            RetValue::FLUSH => {
+                let _ = Selector::close_after_flush(event_loop, socket, 0);
+                return false;
+            }
+            _ => (),
+        }
+        if !is_end && !event.is_read_persist() && event_loop.selector.event_maps.contains_key(&socket) {
+            stop_read(event_loop, event);
+        }
+    }
+
+    if !is_end && event.is_read_full() && event_loop.selector.event_maps.contains_key(&socket) {
+        if !event.entry.read_max_pause {
+            event.buffer.set_end_reason(EndReason::ReadOverflow);
+            let _ = Selector::unregister_socket(event_loop, socket);
+            return false;
+        }
+        let _ = Selector::pause_read(event_loop, socket);
+        return false;
+    }
+    event_loop.selector.event_maps.contains_key(&socket)
+}
+
+/// 写缓冲已清空, 注册了写事件的socket回调write, 非持久的写事件只回调一次
+/// 返回false表示该socket已被移除
+fn write_notify(event_loop: &mut EventLoop, socket: SOCKET) -> bool {
+    let mut event = match event_loop.selector.event_maps.get(&socket) {
+        Some(event) => event.clone(),
+        None => return false,
+    };
+    let event_clone = &mut (*event.clone().inner);
+    let event = &mut (*event.inner);
+    if !event.is_write_notify {
+        return true;
+    }
+    if !event.is_write_persist() {
+        event.is_write_notify = false;
+    }
+    match event.entry.write_cb(event_loop, &mut event_clone.buffer) {
+        RetValue::OVER => {
+            event.buffer.set_end_reason(EndReason::Over);
+            let _ = Selector::unregister_socket(event_loop, socket);
+            false
+        }
+        RetValue::FLUSH => {
+            let _ = Selector::close_after_flush(event_loop, socket, 0);
+            false
+        }
+        _ => event_loop.selector.event_maps.contains_key(&socket),
+    }
+}
+
+/// 写缓冲从高水位降到低水位及以下时回调writable, 发送方可继续发送
+/// 返回false表示该socket已被移除
+fn writable_notify(event_loop: &mut EventLoop, socket: SOCKET) -> bool {
+    let mut event = match event_loop.selector.event_maps.get(&socket) {
+        Some(event) => event.clone(),
+        None => return false,
+    };
+    let event_clone = &mut (*event.clone().inner);
+    let event = &mut (*event.inner);
+    if !event.is_write_blocked || event.buffer.write.len() > event.entry.write_low {
+        return true;
+    }
+    event.is_write_blocked = false;
+    match event.entry.writable_cb(event_loop, &mut event_clone.buffer) {
+        RetValue::OVER => {
+            event.buffer.set_end_reason(EndReason::Over);
+            let _ = Selector::unregister_socket(event_loop, socket);
+            false
+        }
+        RetValue::FLUSH => {
+            let _ = Selector::close_after_flush(event_loop, socket, 0);
+            false
+        }
+        _ => event_loop.selector.event_maps.contains_key(&socket),
+    }
+}
+
+/// 非持久的读事件回调一次后停止监听可读, 通过modify_socket重新加入FLAG_READ后继续读取
+fn stop_read(event_loop: &mut EventLoop, event: &mut Event) {
+    event.entry.ev_events.remove(FLAG_READ);
+    let socket = event.as_raw_socket();
+    if let Err(err) = event_loop.selector.modregister(socket, event.entry.ev_events) {
+        event.buffer.set_error(err);
+        event.buffer.set_end_reason(EndReason::ModifyFailed);
+        let _ = Selector::unregister_socket(event_loop, socket);
+    }
+}
+
+/// 对端半关闭, 停止读取并回调read_eof, 本端的写端也已关闭时结束该socket
+fn read_eof(event_loop: &mut EventLoop, socket: SOCKET) {
+    let mut event = match event_loop.selector.event_maps.get(&socket) {
+        Some(event) => event.clone(),
+        None => return,
+    };
+    let event_clone = &mut (*event.clone().inner);
+    let event = &mut (*event.inner);
+    event.is_read_eof = true;
+    if !event.read_timer.is_none() {
+        event_loop.del_timer(event.read_timer);
+        event.read_timer = TimerId::default();
+    }
+    if event.is_write_closed {
+        event.buffer.end_flags.insert(FLAG_ENDED);
+        event.buffer.set_end_reason(EndReason::Eof);
+        let _ = Selector::unregister_socket(event_loop, socket);
+        return;
+    }
+    stop_read(event_loop, event);
     if !event_loop.selector.event_maps.contains_key(&socket) {
         return;
     }
-    let mut event = event_loop.selector.event_maps.get_mut(&socket).map(|e| e.clone()).unwrap();
+    match event.entry.read_eof_cb(event_loop, &mut event_clone.buffer) {
+        RetValue::OVER => {
+            event.buffer.set_end_reason(EndReason::Over);
+            let _ = Selector::unregister_socket(event_loop, socket);
+        }
+        RetValue::FLUSH => {
+            let _ = Selector::close_after_flush(event_loop, socket, 0);
+        }
+        _ => (),
+    }
+}
+
+/// 写缓冲已清空, 关闭写端并发送FIN, 对端也已半关闭时结束该socket
+fn close_write(event_loop: &mut EventLoop, socket: SOCKET) {
+    let mut event = match event_loop.selector.event_maps.get(&socket) {
+        Some(event) => event.clone(),
+        None => return,
+    };
     let event = &mut (*event.inner);
-    // 无需写入, 则取消写入事件
-    if event.buffer.write.len() == 0 {
-        event.buffer.is_in_write = false;
-        event.entry.ev_events.remove(FLAG_WRITE);
-        let _ = event_loop.selector.modregister(event.as_raw_socket(), event.entry.ev_events);
+    event.is_shutdown_pending = false;
+    event.is_write_closed = true;
+    if let Err(err) = event.buffer.socket.shutdown_write() {
+        event.buffer.set_error(err);
+        event.buffer.set_end_reason(EndReason::WriteError);
+        let _ = Selector::unregister_socket(event_loop, socket);
         return;
     }
-    match event.buffer.socket.write(&event.buffer.write.get_data()[..]) {
-        Ok(len) => {
-            if len <= 0 {
-                let _ = Selector::unregister_socket(
-                    event_loop,
-                    event.buffer.as_raw_socket()
-                );
+    if event.is_read_eof {
+        event.buffer.end_flags.insert(FLAG_ENDED);
+        event.buffer.set_end_reason(EndReason::Eof);
+        let _ = Selector::unregister_socket(event_loop, socket);
+        return;
+    }
+    event.is_write_notify = false;
+    event.entry.ev_events.remove(FLAG_WRITE);
+    let _ = event_loop.selector.modregister(socket, event.entry.ev_events);
+}
+
+/// socket出错(EPOLLERR), 以SO_ERROR的值记录错误后关闭该socket, 连接中的socket由连接回调通知该错误
+fn error_done(event_loop: &mut EventLoop, socket: SOCKET) {
+    let mut event = match event_loop.selector.event_maps.get(&socket) {
+        Some(event) => event.clone(),
+        None => return,
+    };
+    let event = &mut (*event.inner);
+    if event.is_connecting() {
+        connect_done(event_loop, socket, None);
+        return;
+    }
+    let err = match event.buffer.socket.take_error() {
+        Ok(Some(err)) => err,
+        Ok(None) => io::Error::new(ErrorKind::Other, "socket error"),
+        Err(err) => err,
+    };
+    event.buffer.set_error(err);
+    event.buffer.set_end_reason(EndReason::SocketError);
+    let _ = Selector::unregister_socket(event_loop, socket);
+}
+
+/// 连接已挂断(EPOLLHUP), 双向均已关闭, 管道等句柄在写端关闭后也只报告EPOLLHUP
+/// 监听可读时先读完剩余的数据, 然后标记FLAG_ENDED并结束该socket
+fn hup_done(event_loop: &mut EventLoop, socket: SOCKET) {
+    let mut event = match event_loop.selector.event_maps.get(&socket) {
+        Some(event) => event.clone(),
+        None => return,
+    };
+    let event = &mut (*event.inner);
+    if event.is_connecting() {
+        connect_done(event_loop, socket, None);
+        return;
+    }
+    while event.entry.ev_events.contains(FLAG_READ) && !event.is_accept() {
+        read_done(event_loop, socket);
+        if !event_loop.selector.event_maps.contains_key(&socket) {
+            return;
+        }
+    }
+    event.buffer.end_flags.insert(FLAG_ENDED);
+    event.buffer.set_end_reason(EndReason::Hangup);
+    let _ = Selector::unregister_socket(event_loop, socket);
+}
+
+/// 边缘触发模式下写出缓冲中的数据, 直到写完或EAGAIN, EPOLLOUT始终在监听中, 无需修改epoll的监听
+/// 返回当次写出的大小
+fn edge_write(event_loop: &mut EventLoop, event: &mut Event) -> io::Result<usize> {
+    let mut size = 0;
+    while !event.buffer.write.empty() {
+        match event.buffer.socket.write(&event.buffer.write.get_data()[..]) {
+            Ok(0) => return Err(io::Error::new(ErrorKind::WriteZero, "failed to write the buffer")),
+            Ok(len) => {
+                event.buffer.write.drain(len);
+                size += len;
+            }
+            Err(ref err) if err.kind() == ErrorKind::WouldBlock => break,
+            Err(err) => return Err(err),
+        }
+    }
+
+    if event.buffer.write.empty() {
+        event.buffer.is_in_write = false;
+        if !event.write_timer.is_none() {
+            event_loop.del_timer(event.write_timer);
+            event.write_timer = TimerId::default();
+        }
+    } else {
+        // 剩余的数据等待下一次可写通知
+        event.buffer.is_in_write = true;
+        let socket = event.as_raw_socket();
+        if event.write_timer.is_none() {
+            event.write_timer = add_socket_timer(event_loop, socket, event.entry.write_timeout, "write stall timeout")?;
+        } else if size > 0 {
+            event_loop.reset_timer(event.write_timer, event.entry.write_timeout);
+        }
+    }
+    Ok(size)
+}
+
+fn datagram_read_done(event_loop: &mut EventLoop, socket: SOCKET) {
+    let mut event = match event_loop.selector.datagram_maps.get(&socket) {
+        Some(event) => event.clone(),
+        None => return,
+    };
+    let event_clone = &mut (*event.clone());
+    let event = &mut (*event);
+    // 读取所有已到达的数据报, 直到EAGAIN
+    loop {
+        let (len, addr) = match event.socket.recv_from(&mut event.read_cache[..]) {
+            Ok(v) => v,
+            Err(ref e) if e.kind() == ErrorKind::WouldBlock => return,
+            Err(e) => {
+                datagram_error(event_loop, socket, e);
                 return;
             }
-            event.buffer.write.drain(len);
-            //如果写入包为空, 则表示没有数据要进行写入, 取消掉写入事件
-            if event.buffer.write.empty() {
-                event.buffer.is_in_write = false;
-                event.entry.ev_events.remove(FLAG_WRITE);
-                let _ = event_loop.selector.modregister(event.as_raw_socket(), event.entry.ev_events);
+        };
+        let buf = &event_clone.read_cache[..len];
+        let ret = match addr {
+            DatagramAddr::Inet(addr) => event.entry.recv_from_cb(event_loop, socket, buf, addr),
+            DatagramAddr::Unix(addr) => event.entry.recv_from_unix_cb(event_loop, socket, buf, addr),
+        };
+        match ret {
+            RetValue::OVER => {
+                let _ = Selector::unregister_datagram(event_loop, socket);
+                return;
             }
-        },
+            _ => (),
+        }
+        // 回调中可能已移除该socket
+        if !event_loop.selector.datagram_maps.contains_key(&socket) {
+            return;
+        }
+    }
+}
+
+fn datagram_write_done(event_loop: &mut EventLoop, socket: SOCKET) {
+    let mut event = match event_loop.selector.datagram_maps.get(&socket) {
+        Some(event) => event.clone(),
+        None => return,
+    };
+    let event = &mut (*event);
+    while let Some((data, addr)) = event.queue.pop_front() {
+        match event.socket.send_to(&data[..], &addr) {
+            Err(ref e) if e.kind() == ErrorKind::WouldBlock => {
+                event.queue.push_front((data, addr));
+                return;
+            }
+            // 发送失败的数据报丢弃, 错误通知给调用者
+            Err(e) => {
+                datagram_error(event_loop, socket, e);
+                if !event_loop.selector.datagram_maps.contains_key(&socket) {
+                    return;
+                }
+            }
+            Ok(_) => (),
+        }
+    }
+    // 队列已清空, 取消写入事件
+    event.entry.ev_events.remove(FLAG_WRITE);
+    let _ = event_loop.selector.modregister(socket, event.entry.ev_events);
+}
+
+/// 数据报socket上的异步错误, 如对端端口不可达等
+fn datagram_error_done(event_loop: &mut EventLoop, socket: SOCKET) {
+    let err = match event_loop.selector.datagram_maps.get(&socket) {
+        Some(event) => event.socket.take_error(),
+        None => return,
+    };
+    match err {
+        Ok(Some(err)) => datagram_error(event_loop, socket, err),
+        Ok(None) => (),
+        // 无法取出错误时移除该socket, 避免EPOLLERR一直触发
         Err(err) => {
-            event.buffer.error = Err(err);
-            let _ = Selector::unregister_socket(
-                event_loop,
-                event.buffer.as_raw_socket()
-            );
-        },
+            datagram_error(event_loop, socket, err);
+            let _ = Selector::unregister_datagram(event_loop, socket);
+        }
+    }
+}
+
+/// 通知数据报socket的错误, 回调返回RetValue::OVER时移除该socket
+fn datagram_error(event_loop: &mut EventLoop, socket: SOCKET, err: io::Error) {
+    let mut event = match event_loop.selector.datagram_maps.get(&socket) {
+        Some(event) => event.clone(),
+        None => return,
+    };
+    let event = &mut (*event);
+    let ret = event.entry.datagram_error_cb(event_loop, socket, &err);
+    event.last_error = Some(err);
+    match ret {
+        RetValue::OVER => {
+            let _ = Selector::unregister_datagram(event_loop, socket);
+        }
+        _ => (),
+    }
+}
+
+fn fd_done(event_loop: &mut EventLoop, fd: RawFd, kind: EpollEventKind) {
+    let mut event = match event_loop.selector.fd_maps.get(&fd) {
+        Some(event) => event.clone(),
+        None => return,
+    };
+    let event = &mut (*event);
+    match event.entry.ready_cb(event_loop, fd, epoll_to_ioevent(kind)) {
+        RetValue::OVER => {
+            let _ = Selector::unregister_fd(event_loop, fd);
+        }
+        _ => (),
     }
 }
 
+fn signal_done(event_loop: &mut EventLoop, fd: RawFd) {
+    let mut event = match event_loop.selector.signal_maps.get(&fd) {
+        Some(event) => event.clone(),
+        None => return,
+    };
+    let event = &mut (*event);
+    while let Ok(Some(info)) = event.sfd.read_signal() {
+        match event.entry.signal_cb(event_loop, info.ssi_signo as i32) {
+            RetValue::OVER => {
+                let _ = Selector::unregister_signal(event_loop, event.signo);
+                return;
+            }
+            _ => (),
+        }
+        // 回调中可能已移除该信号
+        if !event_loop.selector.signal_maps.contains_key(&fd) {
+            return;
+        }
+    }
+}
 
 impl Selector {
     pub fn new(capacity: usize) -> io::Result<Selector> {
         let epfd = try!(epoll_create());
-        Ok(Selector {
+        let selector = Selector {
             epfd: epfd,
             evts: Events::new(capacity),
             event_maps: HashMap::new(),
-        })
+            signal_maps: HashMap::new(),
+            datagram_maps: HashMap::new(),
+            fd_maps: HashMap::new(),
+            awakener: Arc::new(Awakener::new()?),
+            timer_fd: TimerFd::new(
+                ClockId::CLOCK_MONOTONIC,
+                TimerFlags::TFD_NONBLOCK | TimerFlags::TFD_CLOEXEC,
+            ).map_err(super::from_nix_error)?,
+        };
+        selector.register(selector.awakener.as_raw_fd(), FLAG_READ)?;
+        selector.register(selector.timer_fd.as_raw_fd(), FLAG_READ)?;
+        Ok(selector)
+    }
+
+    /// 设置timerfd在timeout微秒后触发, 以弥补epoll_wait只有毫秒精度的不足
+    fn arm_timer(&self, timeout: u64) -> io::Result<()> {
+        self.timer_fd.set(
+            Expiration::OneShot(TimeSpec::microseconds(timeout as i64)),
+            TimerSetTimeFlags::empty(),
+        ).map_err(super::from_nix_error)
+    }
+
+    /// 获取该主循环的唤醒器
+    pub fn awakener(&self) -> Arc<Awakener> {
+        self.awakener.clone()
     }
 
     /// 获取当前可执行的事件, 并同时处理数据, 返回执行的个数
-    pub fn do_select(event: &mut EventLoop, timeout: usize) -> io::Result<usize> {
-        use std::{isize, slice};
-        let timeout_ms = if timeout as isize >= isize::MAX {
-            isize::MAX
-        } else {
-            timeout as isize
+    /// timeout为等待的微秒数, 为None时一直阻塞直到有事件发生
+    /// 非零的超时由timerfd唤醒, 从而得到微秒级的精度
+    pub fn do_select(event: &mut EventLoop, timeout: Option<u64>) -> io::Result<usize> {
+        use std::slice;
+        let timeout_ms = match timeout {
+            Some(0) => 0,
+            Some(timeout) => {
+                event.selector.arm_timer(timeout)?;
+                -1
+            }
+            None => -1,
         };
 
         let dst = unsafe {
@@ -191,12 +1057,49 @@ impl Selector {
 
         for i in 0..cnt {
             let value = event.selector.evts.events[i];
-            if value.events.contains(EPOLLIN) {
+            if value.data as RawFd == event.selector.awakener.as_raw_fd() {
+                event.selector.awakener.reset();
+                continue;
+            }
+            if value.data as RawFd == event.selector.timer_fd.as_raw_fd() {
+                let mut buf = [0u8; 8];
+                let _ = read(event.selector.timer_fd.as_raw_fd(), &mut buf);
+                continue;
+            }
+            if event.selector.fd_maps.contains_key(&(value.data as RawFd)) {
+                fd_done(event, value.data as RawFd, value.events);
+                continue;
+            }
+            if event.selector.signal_maps.contains_key(&(value.data as RawFd)) {
+                signal_done(event, value.data as RawFd);
+                continue;
+            }
+            if event.selector.datagram_maps.contains_key(&(value.data as SOCKET)) {
+                if value.events.contains(EPOLLERR) {
+                    datagram_error_done(event, value.data as SOCKET);
+                }
+                if value.events.contains(EPOLLIN) {
+                    datagram_read_done(event, value.data as SOCKET);
+                }
+                if value.events.contains(EPOLLOUT) {
+                    datagram_write_done(event, value.data as SOCKET);
+                }
+                continue;
+            }
+            if value.events.contains(EPOLLERR) {
+                error_done(event, value.data as SOCKET);
+                continue;
+            }
+            // 对端半关闭(EPOLLRDHUP)时先读完剩余的数据, 读到EOF后结束该socket
+            if value.events.contains(EPOLLIN) || value.events.contains(EPOLLRDHUP) {
                 read_done(event, value.data as SOCKET);
             }
             if value.events.contains(EPOLLOUT) {
                 write_done(event, value.data as SOCKET);
             }
+            if value.events.contains(EPOLLHUP) {
+                hup_done(event, value.data as SOCKET);
+            }
         }
         Ok(cnt)
     }
@@ -278,19 +1181,26 @@ impl Selector {
         buffer: EventBuffer,
         entry: EventEntry,
     ) -> io::Result<()> {
-        let selector = &mut event_loop.selector;
         let socket = buffer.as_raw_socket();
+        // 同一fd重新注册时移除旧注册的定时器, 避免其到期后关闭新的注册
+        if let Some(mut old) = event_loop.selector.event_maps.remove(&socket) {
+            let old = &mut (*old.inner);
+            old.del_timers(event_loop);
+        }
+        let selector = &mut event_loop.selector;
 
-        if selector.event_maps.contains_key(&socket) {
-            selector.event_maps.remove(&socket);
+        let mut event = Event::new(buffer, entry);
+        // 边缘触发模式下须以非阻塞的方式读写到EAGAIN, 并始终监听可写, 发送数据时不再修改epoll的监听
+        if event.is_edge() {
+            event.buffer.socket.set_nonblocking(true)?;
+            event.entry.ev_events.insert(FLAG_WRITE);
         }
 
         let info = EpollEvent {
-            events: ioevent_to_epoll(entry.ev_events),
+            events: ioevent_to_epoll(event.entry.ev_events),
             data: socket as u64,
         };
 
-        let event = Event::new(buffer, entry);
         selector.event_maps.insert(socket, EventImpl::new(event));
 
         if let Err(e) = epoll_ctl(selector.epfd, EpollOp::EpollCtlAdd, socket as RawFd, &info)
@@ -298,6 +1208,16 @@ impl Selector {
             selector.event_maps.remove(&socket);
             return Err(e);
         }
+
+        let mut ev = selector.event_maps[&socket].clone();
+        let event = &mut (*ev.inner);
+        // 定时器分配失败时撤销该次注册
+        if let Err(e) = event.add_timers(event_loop, socket) {
+            event.del_timers(event_loop);
+            event_loop.selector.event_maps.remove(&socket);
+            let _ = event_loop.selector.deregister(socket, EventFlags::all());
+            return Err(e);
+        }
         Ok(())
     }
 
@@ -316,23 +1236,33 @@ impl Selector {
                 return Ok(())
             }
 
-            let mut ev = &selector.event_maps[&socket];
+            let ev = &selector.event_maps[&socket];
             let event = &mut (*ev.clone().inner);
+            if entry.ev_events.contains(FLAG_WRITE) {
+                event.is_write_notify = !is_del;
+            }
             event.entry.merge(is_del, entry);
+            // 暂停读取期间不监听可读, 恢复时再加入
+            if event.is_read_paused {
+                event.entry.ev_events.remove(FLAG_READ);
+            }
+            // 写缓冲中还有数据, 或者为边缘触发模式时, 保持监听可写
+            if event.buffer.is_in_write || event.is_edge() {
+                event.entry.ev_events.insert(FLAG_WRITE);
+            }
 
-            let info = EpollEvent {
-                events: ioevent_to_epoll(event.entry.ev_events),
-                data: 0,
-            };
-
-            if let Err(e) = epoll_ctl(selector.epfd, EpollOp::EpollCtlDel, socket as RawFd, &info)
-            .map_err(super::from_nix_error) {
+            if let Err(e) = selector.modregister(socket, event.entry.ev_events) {
                 Err(e)
             } else {
                 return Ok(())
             }
         };
-        Self::unregister_socket(event_loop, socket)?;
+        // 修改监听失败则关闭该socket, 结束原因只在此关闭时记录
+        if let Some(ev) = event_loop.selector.event_maps.get(&socket) {
+            let event = &mut (*ev.clone().inner);
+            event.buffer.set_end_reason(EndReason::ModifyFailed);
+        }
+        let _ = Self::unregister_socket(event_loop, socket);
         return err;
     }
 
@@ -345,15 +1275,346 @@ impl Selector {
         if let Some(mut event) = event_loop.selector.event_maps.remove(&socket) {
             let event_clone = &mut (*event.clone().inner);
             let event = &mut (*event.inner);
+            event.del_timers(event_loop);
             event_clone.buffer.socket.close();
+            event_clone.buffer.set_end_reason(EndReason::Unregister);
             event.entry.end_cb(event_loop, &mut event_clone.buffer);
         }
         let _ = event_loop.selector.deregister(socket, EventFlags::all())?;
         Ok(())
     }
 
+    /// socket的超时定时器到期, 记录超时错误后关闭该socket, 连接中的socket由连接回调通知该错误
+    /// time_id不是该socket当前的定时器时(如fd已被新的注册复用)不做处理
+    fn timeout_socket(event_loop: &mut EventLoop, socket: SOCKET, time_id: TimerId, reason: &'static str) {
+        let mut event = match event_loop.selector.event_maps.get(&socket) {
+            Some(event) => event.clone(),
+            None => return,
+        };
+        let event = &mut (*event.inner);
+        if !event.has_timer(time_id) {
+            return;
+        }
+        if event.is_connecting() {
+            connect_done(event_loop, socket, Some(io::Error::new(ErrorKind::TimedOut, reason)));
+            return;
+        }
+        event.buffer.set_error(io::Error::new(ErrorKind::TimedOut, reason));
+        event.buffer.end_flags.insert(FLAG_TIMEOUT);
+        event.buffer.set_end_reason(EndReason::Timeout);
+        let _ = Selector::unregister_socket(event_loop, socket);
+    }
+
+    /// 注册信号事件, 在本线程屏蔽该信号并创建signalfd加入到epoll的监听中
+    /// 如果该信号已注册, 则替换原有的回调
+    pub fn register_signal(
+        event_loop: &mut EventLoop,
+        signo: i32,
+        entry: EventEntry,
+    ) -> io::Result<()> {
+        Self::unregister_signal(event_loop, signo)?;
+
+        let signal = Signal::try_from(signo).map_err(super::from_nix_error)?;
+        let was_blocked = SigSet::thread_get_mask()
+            .map_err(super::from_nix_error)?
+            .contains(signal);
+        let mut mask = SigSet::empty();
+        mask.add(signal);
+        mask.thread_block().map_err(super::from_nix_error)?;
+
+        let sfd = match SignalFd::with_flags(&mask, SfdFlags::SFD_NONBLOCK | SfdFlags::SFD_CLOEXEC) {
+            Ok(sfd) => sfd,
+            Err(e) => {
+                if !was_blocked {
+                    let _ = mask.thread_unblock();
+                }
+                return Err(super::from_nix_error(e));
+            }
+        };
+
+        let fd = sfd.as_raw_fd();
+        if let Err(e) = event_loop.selector.register(fd, FLAG_READ) {
+            if !was_blocked {
+                let _ = mask.thread_unblock();
+            }
+            return Err(e);
+        }
+
+        event_loop.selector.signal_maps.insert(fd, FromRawArc::new(SignalEvent {
+            signo: signo,
+            sfd: sfd,
+            entry: entry,
+            was_blocked: was_blocked,
+        }));
+        Ok(())
+    }
+
+    /// 取消信号的监听, 并恢复该信号原先的屏蔽状态
+    pub fn unregister_signal(
+        event_loop: &mut EventLoop,
+        signo: i32,
+    ) -> io::Result<()> {
+        let fd = match event_loop.selector.signal_maps.iter().find(|&(_, ev)| ev.signo == signo) {
+            Some((fd, _)) => *fd,
+            None => return Ok(()),
+        };
+        let event = event_loop.selector.signal_maps.remove(&fd).unwrap();
+        let _ = event_loop.selector.deregister(fd, EventFlags::all());
+        if !event.was_blocked {
+            let mut mask = SigSet::empty();
+            mask.add(Signal::try_from(signo).map_err(super::from_nix_error)?);
+            mask.thread_unblock().map_err(super::from_nix_error)?;
+        }
+        Ok(())
+    }
+
+    /// 注册任意句柄, 加入到epoll的监听中, 如果该句柄已注册, 则替换原有的回调
+    pub fn register_fd(
+        event_loop: &mut EventLoop,
+        entry: EventEntry,
+    ) -> io::Result<()> {
+        let fd = entry.ev_fd;
+        Self::unregister_fd(event_loop, fd)?;
+        event_loop.selector.register(fd, entry.ev_events)?;
+        event_loop.selector.fd_maps.insert(fd, FromRawArc::new(FdEvent {
+            fd: fd,
+            entry: entry,
+        }));
+        Ok(())
+    }
+
+    /// 修改句柄监听的事件
+    pub fn modify_fd(
+        event_loop: &mut EventLoop,
+        fd: RawFd,
+        ev_events: EventFlags,
+    ) -> io::Result<()> {
+        let mut event = match event_loop.selector.fd_maps.get(&fd) {
+            Some(event) => event.clone(),
+            None => {
+                return Err(io::Error::new(
+                    ErrorKind::NotFound,
+                    "the fd is not registered",
+                ))
+            }
+        };
+        let event = &mut (*event);
+        event_loop.selector.modregister(fd, ev_events)?;
+        event.entry.ev_events = ev_events;
+        Ok(())
+    }
+
+    /// 取消句柄的监听, 句柄由调用者负责关闭
+    pub fn unregister_fd(
+        event_loop: &mut EventLoop,
+        fd: RawFd,
+    ) -> io::Result<()> {
+        if event_loop.selector.fd_maps.remove(&fd).is_some() {
+            event_loop.selector.deregister(fd, EventFlags::all())?;
+        }
+        Ok(())
+    }
+
+    /// 注册数据报socket, 把socket设为非阻塞并加入到epoll的监听中
+    pub fn register_datagram(
+        event_loop: &mut EventLoop,
+        socket: DatagramSocket,
+        entry: EventEntry,
+    ) -> io::Result<()> {
+        let fd = socket.as_raw_fd();
+        socket.set_nonblocking(true)?;
+        Self::unregister_datagram(event_loop, fd)?;
+        event_loop.selector.register(fd, entry.ev_events)?;
+        event_loop.selector.datagram_maps.insert(fd, FromRawArc::new(DatagramEvent {
+            socket: socket,
+            entry: entry,
+            queue: VecDeque::new(),
+            read_cache: vec![0; 65536],
+            last_error: None,
+        }));
+        Ok(())
+    }
+
+    /// 取消数据报socket的监听, 该socket在此关闭
+    pub fn unregister_datagram(
+        event_loop: &mut EventLoop,
+        socket: SOCKET,
+    ) -> io::Result<()> {
+        if event_loop.selector.datagram_maps.contains_key(&socket) {
+            let ret = event_loop.selector.deregister(socket, EventFlags::all());
+            event_loop.selector.datagram_maps.remove(&socket);
+            ret?;
+        }
+        Ok(())
+    }
+
+    /// 取出数据报socket最近一次出错的原因
+    pub fn take_datagram_error(event_loop: &mut EventLoop, socket: SOCKET) -> Option<io::Error> {
+        let mut event = match event_loop.selector.datagram_maps.get(&socket) {
+            Some(event) => event.clone(),
+            None => return None,
+        };
+        let event = &mut (*event);
+        event.last_error.take()
+    }
+
+    /// 通过数据报socket发送数据, 队列为空时直接发送, 内核缓冲已满则放入队列并监听可写事件
+    pub fn send_to(event_loop: &mut EventLoop, socket: &SOCKET, data: &[u8], addr: &DatagramAddr) -> io::Result<usize> {
+        let mut event = match event_loop.selector.datagram_maps.get(socket) {
+            Some(event) => event.clone(),
+            None => {
+                return Err(io::Error::new(
+                    ErrorKind::Other,
+                    "the socket already be remove",
+                ))
+            }
+        };
+        let event = &mut (*event);
+        // 保证数据报的发送顺序, 队列不为空时直接追加到队尾
+        if event.queue.is_empty() {
+            match event.socket.send_to(data, addr) {
+                Err(ref e) if e.kind() == ErrorKind::WouldBlock => (),
+                ret => return ret,
+            }
+        }
+        event.queue.push_back((data.to_vec(), addr.clone()));
+        if !event.entry.ev_events.contains(FLAG_WRITE) {
+            event.entry.ev_events.insert(FLAG_WRITE);
+            event_loop.selector.modregister(*socket, event.entry.ev_events)?;
+        }
+        Ok(0)
+    }
+
+    /// 关闭socket的写端, 写缓冲中还有数据时等待写完后再发送FIN, 之后不能再发送数据
+    /// 对端也已半关闭时结束该socket
+    pub fn shutdown_write(event_loop: &mut EventLoop, socket: SOCKET) -> io::Result<()> {
+        let mut event = match event_loop.selector.event_maps.get(&socket) {
+            Some(event) => event.clone(),
+            None => {
+                return Err(io::Error::new(
+                    ErrorKind::Other,
+                    "the socket already be remove",
+                ))
+            }
+        };
+        let event = &mut (*event.inner);
+        if event.is_shutdown_pending || event.is_write_closed {
+            return Ok(());
+        }
+        if event.buffer.write.empty() && !event.is_connecting() {
+            close_write(event_loop, socket);
+            return Ok(());
+        }
+        // 连接完成或写缓冲清空后在写事件中关闭写端
+        event.is_shutdown_pending = true;
+        event.entry.ev_events.insert(FLAG_WRITE);
+        Ok(())
+    }
+
+    /// 停止读取, 等待写缓冲中的数据发送完毕后关闭socket, 并以EndReason::Flushed调用结束回调
+    /// timeout为等待的最长时间, 单位为微秒, 为0表示不限制, 超时后以TimedOut错误关闭该socket
+    pub fn close_after_flush(event_loop: &mut EventLoop, socket: SOCKET, timeout: u64) -> io::Result<()> {
+        let mut event = match event_loop.selector.event_maps.get(&socket) {
+            Some(event) => event.clone(),
+            None => {
+                return Err(io::Error::new(
+                    ErrorKind::Other,
+                    "the socket already be remove",
+                ))
+            }
+        };
+        let event = &mut (*event.inner);
+        if event.is_closing {
+            return Ok(());
+        }
+        if (event.buffer.write.empty() && !event.is_connecting()) || event.is_write_closed {
+            event.buffer.set_end_reason(EndReason::Flushed);
+            return Self::unregister_socket(event_loop, socket);
+        }
+
+        event.is_closing = true;
+        if !event.read_timer.is_none() {
+            event_loop.del_timer(event.read_timer);
+            event.read_timer = TimerId::default();
+        }
+        event.flush_timer = add_socket_timer(event_loop, socket, timeout, "flush timeout")?;
+        event.entry.ev_events.remove(FLAG_READ);
+        event.entry.ev_events.insert(FLAG_WRITE);
+        if let Err(err) = event_loop.selector.modregister(socket, event.entry.ev_events) {
+            event.buffer.set_error(err);
+            event.buffer.set_end_reason(EndReason::ModifyFailed);
+            let _ = Self::unregister_socket(event_loop, socket);
+        }
+        Ok(())
+    }
+
+    /// 暂停读取socket, 从epoll的监听中移除可读, 缓冲, 回调及数据均保持不变, 读空闲定时器同时暂停
+    /// 未在监听可读(如非持久的读事件已回调, 对端已半关闭)时不做处理
+    pub fn pause_read(event_loop: &mut EventLoop, socket: SOCKET) -> io::Result<()> {
+        let mut event = match event_loop.selector.event_maps.get(&socket) {
+            Some(event) => event.clone(),
+            None => {
+                return Err(io::Error::new(
+                    ErrorKind::Other,
+                    "the socket already be remove",
+                ))
+            }
+        };
+        let event = &mut (*event.inner);
+        if event.is_read_paused || !event.entry.ev_events.contains(FLAG_READ) {
+            return Ok(());
+        }
+        event.is_read_paused = true;
+        if !event.read_timer.is_none() {
+            event_loop.del_timer(event.read_timer);
+            event.read_timer = TimerId::default();
+        }
+        event.entry.ev_events.remove(FLAG_READ);
+        if let Err(err) = event_loop.selector.modregister(socket, event.entry.ev_events) {
+            event.buffer.set_end_reason(EndReason::ModifyFailed);
+            let _ = Self::unregister_socket(event_loop, socket);
+            return Err(err);
+        }
+        Ok(())
+    }
+
+    /// 恢复被pause_read暂停的读取, 重新监听可读并重启读空闲定时器
+    /// 读缓冲中已有达到低水位的数据时, 在下一次循环中回调read
+    pub fn resume_read(event_loop: &mut EventLoop, socket: SOCKET) -> io::Result<()> {
+        let mut event = match event_loop.selector.event_maps.get(&socket) {
+            Some(event) => event.clone(),
+            None => {
+                return Err(io::Error::new(
+                    ErrorKind::Other,
+                    "the socket already be remove",
+                ))
+            }
+        };
+        let event = &mut (*event.inner);
+        if !event.is_read_paused {
+            return Ok(());
+        }
+        event.is_read_paused = false;
+        if event.is_read_eof || event.is_closing {
+            return Ok(());
+        }
+        event.entry.ev_events.insert(FLAG_READ);
+        if let Err(err) = event_loop.selector.modregister(socket, event.entry.ev_events) {
+            event.buffer.set_end_reason(EndReason::ModifyFailed);
+            let _ = Self::unregister_socket(event_loop, socket);
+            return Err(err);
+        }
+        event.read_timer = add_socket_timer(event_loop, socket, event.entry.read_timeout, "read idle timeout")?;
+        // 暂停前留在读缓冲中的数据不会再有可读通知, 直接交给读回调
+        if event.is_read_enough() {
+            let _ = read_notify(event_loop, socket, false);
+        }
+        Ok(())
+    }
+
     // 给指定的socket发送数据, 如果不能一次发送完毕则会写入到缓存中, 等待下次继续发送
     // 返回值为指定的当次的写入大小, 如果没有全部写完数据, 则下次写入先写到缓冲中, 等待系统的可写通知
+    // 写阻塞定时器分配失败时返回该错误, 已写入缓冲的数据仍会继续发送
     pub fn send_socket(event_loop: &mut EventLoop, socket: &SOCKET, data: &[u8]) -> io::Result<usize> {
         if !event_loop.selector.event_maps.contains_key(&socket) {
             return Err(io::Error::new(
@@ -363,13 +1624,50 @@ impl Selector {
         }
         let mut event = event_loop.selector.event_maps.get_mut(&socket).map(|e| e.clone()).unwrap();
         let event = &mut (*event.inner);
+        if event.is_shutdown_pending || event.is_write_closed || event.is_closing {
+            return Err(io::Error::new(
+                ErrorKind::BrokenPipe,
+                "the socket write side is shut down",
+            ));
+        }
+        if event.is_write_blocked {
+            return Err(io::Error::new(
+                ErrorKind::WouldBlock,
+                "the write buffer is above the high watermark",
+            ));
+        }
         event.buffer.write.write(data)?;
         if event.buffer.is_in_write || event.buffer.write.empty() {
+            event.check_write_high();
             return Ok(0);
         }
+        // 边缘触发模式下直接写出, 写不完的部分等待可写通知
+        if event.is_edge() {
+            if event.is_connecting() {
+                event.check_write_high();
+                return Ok(0);
+            }
+            return match edge_write(event_loop, event) {
+                Ok(size) => {
+                    event.check_write_high();
+                    Ok(size)
+                }
+                Err(err) => {
+                    let kind = err.kind();
+                    event.buffer.set_error(err);
+                    event.buffer.set_end_reason(EndReason::WriteError);
+                    let _ = Selector::unregister_socket(event_loop, *socket);
+                    Err(io::Error::from(kind))
+                }
+            };
+        }
+        event.check_write_high();
         event.entry.ev_events.insert(FLAG_WRITE);
         event.buffer.is_in_write = true;
         event_loop.selector.modregister(event.as_raw_socket(), event.entry.ev_events)?;
+        if event.write_timer.is_none() {
+            event.write_timer = add_socket_timer(event_loop, *socket, event.entry.write_timeout, "write stall timeout")?;
+        }
         Ok(0)
     }
 }
@@ -378,17 +1676,48 @@ impl Selector {
 fn ioevent_to_epoll(ev_events: EventFlags) -> EpollEventKind {
     let mut kind = EpollEventKind::empty();
 
+    // 连接中的socket只关心可写, 以此得知连接的结果
+    if ev_events.contains(FLAG_CONNECT) {
+        kind.insert(EPOLLOUT);
+        return kind;
+    }
+
     if ev_events.contains(FLAG_READ) {
         kind.insert(EPOLLIN);
+        kind.insert(EPOLLRDHUP);
     }
 
     if ev_events.contains(FLAG_WRITE) {
         kind.insert(EPOLLOUT);
     }
-    // kind.insert(EPOLLET);
+
+    if ev_events.contains(FLAG_EDGE) {
+        kind.insert(EPOLLET);
+    }
     kind
 }
 
+fn epoll_to_ioevent(kind: EpollEventKind) -> EventFlags {
+    let mut ev_events = EventFlags::empty();
+
+    if kind.contains(EPOLLIN) {
+        ev_events.insert(FLAG_READ);
+    }
+
+    if kind.contains(EPOLLOUT) {
+        ev_events.insert(FLAG_WRITE);
+    }
+
+    if kind.contains(EPOLLERR) {
+        ev_events.insert(FLAG_ERROR);
+    }
+
+    if kind.contains(EPOLLHUP) || kind.contains(EPOLLRDHUP) {
+        ev_events.insert(FLAG_ENDED);
+    }
+    ev_events
+}
+
 impl Drop for Selector {
     fn drop(&mut self) {
         let _ = close(self.epfd);
@@ -1,7 +1,7 @@
 #![allow(dead_code)]
 use std::os::unix::io::RawFd;
 use std::io::{self, ErrorKind};
-use {EventEntry, EventFlags, EventBuffer, EventLoop, RetValue};
+use {EventEntry, EventFlags, EventBuffer, EndReason, EventLoop, RetValue};
 
 use libc::{timespec, time_t, c_long};
 
@@ -76,6 +76,7 @@ fn read_done(event_loop: &mut EventLoop, socket: SOCKET) {
 
         match ret {
             RetValue::OVER => {
+                event.buffer.set_end_reason(EndReason::Over);
                 let _ = event_loop.unregister_socket(event.as_raw_socket());
             }
             _ => {
@@ -86,6 +87,8 @@ fn read_done(event_loop: &mut EventLoop, socket: SOCKET) {
         match event.buffer.socket.read(&mut event.buffer.read_cache[..]) {
             Ok(len) => {
                 if len <= 0 {
+                    event.buffer.end_flags.insert(EventFlags::FLAG_ENDED);
+                    event.buffer.set_end_reason(EndReason::Eof);
                     let _ = Selector::unregister_socket(
                         event_loop,
                         event.buffer.as_raw_socket()
@@ -101,6 +104,7 @@ fn read_done(event_loop: &mut EventLoop, socket: SOCKET) {
                 if event.buffer.has_read_buffer() {
                     match event.entry.read_cb(event_loop, &mut event_clone.buffer) {
                         RetValue::OVER => {
+                            event.buffer.set_end_reason(EndReason::Over);
                             let _ = event_loop.unregister_socket(event.as_raw_socket());
                             return;
                         }
@@ -109,7 +113,8 @@ fn read_done(event_loop: &mut EventLoop, socket: SOCKET) {
                 }
             },
             Err(err) => {
-                event.buffer.error = Err(err);
+                event.buffer.set_error(err);
+                event.buffer.set_end_reason(EndReason::ReadError);
                 let _ = Selector::unregister_socket(
                     event_loop,
                     event.buffer.as_raw_socket()
@@ -135,6 +140,7 @@ fn write_done(event_loop: &mut EventLoop, socket: SOCKET) {
     match event.buffer.socket.write(&event.buffer.write.get_data()[..]) {
         Ok(len) => {
             if len <= 0 {
+                event.buffer.set_end_reason(EndReason::WriteError);
                 let _ = Selector::unregister_socket(
                     event_loop,
                     event.buffer.as_raw_socket()
@@ -150,7 +156,8 @@ fn write_done(event_loop: &mut EventLoop, socket: SOCKET) {
             }
         },
         Err(err) => {
-            event.buffer.error = Err(err);
+            event.buffer.set_error(err);
+            event.buffer.set_end_reason(EndReason::WriteError);
             let _ = Selector::unregister_socket(
                 event_loop,
                 event.buffer.as_raw_socket()
@@ -173,23 +180,17 @@ impl Selector {
 
 
     /// 获取当前可执行的事件, 并同时处理数据, 返回执行的个数
-    pub fn do_select(event: &mut EventLoop, timeout: usize) -> io::Result<usize> {
-
-        use std::isize;
-
-        let timeout_ms = if timeout as isize >= isize::MAX {
-            isize::MAX
-        } else {
-            timeout as isize
-        };
-
-        let timeout = timespec {
-            tv_sec: (timeout_ms / 1000) as time_t,
-            tv_nsec: ((timeout_ms % 1000) * 1_000_000) as c_long,
-        };
+    /// timeout为等待的微秒数, 为None时一直阻塞直到有事件发生
+    pub fn do_select(event: &mut EventLoop, timeout: Option<u64>) -> io::Result<usize> {
+        let timeout = timeout.map(|timeout| {
+            timespec {
+                tv_sec: (timeout / 1_000_000) as time_t,
+                tv_nsec: ((timeout % 1_000_000) * 1000) as c_long,
+            }
+        });
 
         let cnt = try!(
-            kevent_ts(event.selector.kq, &[], event.selector.evts.as_mut_slice(), Some(timeout))
+            kevent_ts(event.selector.kq, &[], event.selector.evts.as_mut_slice(), timeout)
                 .map_err(super::from_nix_error)
         );
         unsafe {
@@ -343,20 +344,22 @@ impl Selector {
                 return Ok(())
             }
 
-            let ev_events = {
-                let ev = &selector.event_maps[&socket];
-                let event = &mut (*ev.clone().inner);
-                event.entry.merge(is_del, entry);
-                event.entry.ev_events
-            };
+            let mut ev = selector.event_maps[&socket].clone();
+            let event = &mut (*ev.inner);
+            event.entry.merge(is_del, entry);
 
-            if let Err(e) = selector.register(socket, ev_events) {
+            if let Err(e) = selector.register(socket, event.entry.ev_events) {
                 Err(e)
             } else {
                 return Ok(())
             }
         };
-        Self::unregister_socket(event_loop, socket)?;
+        // 修改监听失败则关闭该socket, 结束原因只在此关闭时记录
+        if let Some(ev) = event_loop.selector.event_maps.get(&socket) {
+            let event = &mut (*ev.clone().inner);
+            event.buffer.set_end_reason(EndReason::ModifyFailed);
+        }
+        let _ = Self::unregister_socket(event_loop, socket);
         return err;
     }
 
@@ -369,6 +372,7 @@ impl Selector {
             let event_clone = &mut (*event.clone().inner);
             let event = &mut (*event.inner);
             event_clone.buffer.socket.close();
+            event_clone.buffer.set_end_reason(EndReason::Unregister);
             event.entry.end_cb(event_loop, &mut event_clone.buffer);
         }
         let _ = event_loop.selector.deregister(socket, EventFlags::all())?;
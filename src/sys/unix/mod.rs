@@ -3,7 +3,7 @@
 mod epoll;
 
 #[cfg(any(target_os = "linux", target_os = "android"))]
-pub use self::epoll::{Events, Selector};
+pub use self::epoll::{Events, Selector, Awakener};
 
 #[cfg(any(target_os = "bitrig", target_os = "dragonfly", target_os = "freebsd",
             target_os = "ios", target_os = "macos", target_os = "netbsd", target_os = "openbsd"))]
@@ -12,6 +12,56 @@ mod kqueue;
             target_os = "ios", target_os = "macos", target_os = "netbsd", target_os = "openbsd"))]
 pub use self::kqueue::{Events, Selector};
 
+/// 以非阻塞方式连接Unix域socket, 返回的bool表示连接是否仍在进行中
+/// Linux下本地连接不会返回EINPROGRESS, 对端的连接队列已满时返回WouldBlock错误
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub fn connect_unix_asyn(
+    addr: &::std::os::unix::net::SocketAddr,
+) -> ::std::io::Result<(::std::os::unix::net::UnixStream, bool)> {
+    use std::{io, mem};
+    use std::os::linux::net::SocketAddrExt;
+    use std::os::unix::ffi::OsStrExt;
+    use std::os::unix::io::FromRawFd;
+    use std::os::unix::net::UnixStream;
+
+    let mut sun: ::libc::sockaddr_un = unsafe { mem::zeroed() };
+    sun.sun_family = ::libc::AF_UNIX as ::libc::sa_family_t;
+    // 抽象命名空间的地址以\0开头, 文件路径以\0结尾
+    let (name, offset, tail) = if let Some(path) = addr.as_pathname() {
+        (path.as_os_str().as_bytes(), 0, 1)
+    } else if let Some(name) = addr.as_abstract_name() {
+        (name, 1, 0)
+    } else {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "unnamed unix socket address"));
+    };
+    if offset + name.len() + tail > sun.sun_path.len() {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "unix socket path too long"));
+    }
+    for (i, b) in name.iter().enumerate() {
+        sun.sun_path[offset + i] = *b as ::libc::c_char;
+    }
+    let len = mem::size_of::<::libc::sa_family_t>() + offset + name.len() + tail;
+
+    let fd = unsafe {
+        ::libc::socket(::libc::AF_UNIX, ::libc::SOCK_STREAM | ::libc::SOCK_NONBLOCK | ::libc::SOCK_CLOEXEC, 0)
+    };
+    if fd < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    let stream = unsafe { UnixStream::from_raw_fd(fd) };
+    let ret = unsafe {
+        ::libc::connect(fd, &sun as *const _ as *const ::libc::sockaddr, len as ::libc::socklen_t)
+    };
+    if ret == 0 {
+        return Ok((stream, false));
+    }
+    let err = io::Error::last_os_error();
+    if err.raw_os_error() == Some(::libc::EINPROGRESS) {
+        return Ok((stream, true));
+    }
+    Err(err)
+}
+
 pub fn from_nix_error(err: ::nix::Error) -> ::std::io::Error {
     ::std::io::Error::from_raw_os_error(::nix::errno::errno() as i32)
 }
@@ -1,4 +1,4 @@
-use {EventEntry, EventFlags, EventBuffer, EventLoop, RetValue};
+use {EventEntry, EventFlags, EventBuffer, EndReason, EventLoop, RetValue};
 use std::collections::HashMap;
 use std::mem;
 use psocket::SOCKET;
@@ -124,8 +124,8 @@ fn read_done(event_loop: &mut EventLoop, status: &OVERLAPPED_ENTRY) {
     let mut event = overlapped2arc!(status.overlapped(), Event, read);
     if event.is_accept() {
         let mut socket = event.accept_socket.take().unwrap();
-        let result = event.buffer.socket.accept_complete(&socket).and_then(|()| {
-            event.accept_buf.as_ref().unwrap().parse(&event.buffer.socket)
+        let result = event.buffer.socket.as_tcp().unwrap().accept_complete(&socket).and_then(|()| {
+            event.accept_buf.as_ref().unwrap().parse(event.buffer.socket.as_tcp().unwrap())
         }).and_then(|buf| {
             buf.remote().ok_or_else(|| {
                 io::Error::new(ErrorKind::Other, "could not obtain remote address")
@@ -141,6 +141,7 @@ fn read_done(event_loop: &mut EventLoop, status: &OVERLAPPED_ENTRY) {
         };
         match ret {
             RetValue::OVER => {
+                event.buffer.set_end_reason(EndReason::Over);
                 let _ = event_loop.unregister_socket(event.as_raw_socket());
             }
             _ => {
@@ -158,7 +159,8 @@ fn read_done(event_loop: &mut EventLoop, status: &OVERLAPPED_ENTRY) {
                     event.as_raw_socket(),
                 )
                 {
-                    event.buffer.error = Err(err);
+                    event.buffer.set_error(err);
+                    event.buffer.set_end_reason(EndReason::ReadError);
                     let _ = event_loop.unregister_socket(event.as_raw_socket());
                 } else {
                     event.buffer.is_in_read = true;
@@ -177,6 +179,8 @@ fn read_done(event_loop: &mut EventLoop, status: &OVERLAPPED_ENTRY) {
         }
 
         if bytes_transferred == 0 {
+            event.buffer.end_flags.insert(EventFlags::FLAG_ENDED);
+            event.buffer.set_end_reason(EndReason::Eof);
             let _ = Selector::unregister_socket(
                 event_loop,
                 event.buffer.as_raw_socket()
@@ -194,6 +198,7 @@ fn read_done(event_loop: &mut EventLoop, status: &OVERLAPPED_ENTRY) {
         if event.buffer.has_read_buffer() {
             match event.entry.read_cb(event_loop, &mut event_clone.buffer) {
                 RetValue::OVER => {
+                    event.buffer.set_end_reason(EndReason::Over);
                     let _ = event_loop.unregister_socket(event.as_raw_socket());
                     return;
                 }
@@ -213,7 +218,8 @@ fn read_done(event_loop: &mut EventLoop, status: &OVERLAPPED_ENTRY) {
             &event.as_raw_socket(),
         ) {
             Err(e) => {
-                event.buffer.error = Err(e);
+                event.buffer.set_error(e);
+                event.buffer.set_end_reason(EndReason::ReadError);
                 let _ = event_loop.unregister_socket(event.as_raw_socket());
             },
             _ => {
@@ -231,6 +237,7 @@ fn write_done(event_loop: &mut EventLoop, status: &OVERLAPPED_ENTRY) {
 
     match event.entry.write_cb(event_loop, &mut event_clone.buffer) {
         RetValue::OVER => {
+            event.buffer.set_end_reason(EndReason::Over);
             let _ = event_loop.unregister_socket(event.as_raw_socket());
             return;
         }
@@ -261,10 +268,11 @@ impl Selector {
     }
 
     /// 获取当前可执行的事件, 并同时处理数据, 返回执行的个数
-    pub fn do_select(event: &mut EventLoop, timeout: usize) -> io::Result<usize> {
+    /// timeout为等待的微秒数, 为None时一直阻塞直到有事件发生
+    pub fn do_select(event: &mut EventLoop, timeout: Option<u64>) -> io::Result<usize> {
         let n = match event.selector.port.get_many(
             &mut event.selector.events.statuses,
-            Some(Duration::from_millis(timeout as u64)),
+            timeout.map(|timeout| Duration::from_micros(timeout)),
         ) {
             Ok(statuses) => statuses.len(),
             Err(ref e) if e.raw_os_error() == Some(WAIT_TIMEOUT as i32) => 0,
@@ -293,13 +301,13 @@ impl Selector {
             if event.buffer.is_in_read {
                 return Ok(());
             }
-            let addr = event.buffer.socket.local_addr()?;
+            let addr = event.buffer.socket.as_tcp().unwrap().local_addr()?;
             event.accept_socket = Some(match addr {
                 SocketAddr::V4(..) => TcpSocket::new_v4()?,
                 SocketAddr::V6(..) => TcpSocket::new_v6()?,
             });
             unsafe {
-                event.buffer.socket.accept_overlapped(
+                event.buffer.socket.as_tcp().unwrap().accept_overlapped(
                     &event.accept_socket.as_ref().unwrap(),
                     event.accept_buf.as_mut().unwrap(),
                     event.read.as_mut_ptr(),
@@ -323,7 +331,7 @@ impl Selector {
                 return Ok(());
             }
             unsafe {
-                event.buffer.socket.read_overlapped(
+                event.buffer.socket.as_tcp().unwrap().read_overlapped(
                     &mut event.buffer.read_cache[..],
                     event.read.as_mut_ptr(),
                 )?
@@ -348,7 +356,7 @@ impl Selector {
 
             let write = event.write.as_mut_ptr();
             let res = unsafe {
-                event.buffer.socket.write_overlapped(
+                event.buffer.socket.as_tcp().unwrap().write_overlapped(
                     &event.buffer.write.get_data()[..],
                     write,
                 )?
@@ -410,7 +418,7 @@ impl Selector {
             selector.event_maps.remove(&socket);
         }
 
-        selector.port.add_socket(entry.ev_events, &buffer.socket)?;
+        selector.port.add_socket(entry.ev_events, buffer.socket.as_tcp().unwrap())?;
         let event = Event::new(buffer, entry);
         selector.event_maps.insert(socket, EventImpl::new(event));
         if let Err(e) = selector.check_socket_event(socket) {
@@ -444,7 +452,12 @@ impl Selector {
                 return Ok(())
             }
         };
-        Self::unregister_socket(event_loop, socket)?;
+        // 修改监听失败则关闭该socket, 结束原因只在此关闭时记录
+        if let Some(ev) = event_loop.selector.event_maps.get(&socket) {
+            let event = &mut (*ev.clone().inner);
+            event.buffer.set_end_reason(EndReason::ModifyFailed);
+        }
+        let _ = Self::unregister_socket(event_loop, socket);
         return err;
     }
 
@@ -457,6 +470,7 @@ impl Selector {
         if let Some(mut ev) = event_loop.selector.event_maps.remove(&socket) {
             let event = &mut (*ev.clone().inner);
             let event_clone = &mut (*ev.inner);
+            event_clone.buffer.set_end_reason(EndReason::Unregister);
             event.entry.end_cb(event_loop, &mut event_clone.buffer);
         }
         Ok(())
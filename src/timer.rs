@@ -1,6 +1,7 @@
-use {EventEntry, now_micro};
+use {EventEntry, now_monotonic};
+use timer_wheel::TimerWheel;
 use std::fmt;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::io::{self, ErrorKind};
 use std::cmp::{Ord, Ordering};
 use std::collections::HashMap;
 use rbtree::RBTree;
@@ -8,15 +9,81 @@ use rbtree::RBTree;
 extern crate libc;
 extern crate time;
 
+/// 定时器的实现方式
+#[derive(Copy, Clone, Debug)]
+pub enum TimerBackend {
+    /// 红黑树, 增删及取出均为O(logn)
+    Tree,
+    /// 分层时间轮, 增删为O(1), tick_us为每格的时间粒度(微秒), 超出时间轮范围的定时器存放在红黑树中
+    Wheel { tick_us: u64 },
+}
+
+/// 重复定时器的重新调度方式
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum TimerRepeat {
+    /// 固定延迟, 回调结束后从当前时间起再过tick_step到期, 会累积回调耗时及主循环的延迟
+    FixedDelay,
+    /// 固定频率, 下次到期时间为上次到期时间加tick_step, 错过的调用按MissedTick处理
+    FixedRate(MissedTick),
+}
+
+impl Default for TimerRepeat {
+    fn default() -> TimerRepeat {
+        TimerRepeat::FixedDelay
+    }
+}
+
+/// 固定频率的定时器错过到期时间时的处理方式
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum MissedTick {
+    /// 逐次补回错过的调用, 直到追上当前时间
+    CatchUp,
+    /// 丢弃错过的调用, 等待下一个未到的到期时间
+    Skip,
+    /// 把错过的调用合并为立即执行的一次, 之后仍按原来的频率执行
+    Coalesce,
+}
+
+/// 定时器的句柄, 低32位为槽位id, 高32位为该槽位的代数
+/// 槽位id在回绕后会被重新使用, 但代数不同, 因此已失效的句柄不会误操作新的定时器
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct TimerId(pub u64);
+
+impl TimerId {
+    pub fn new(slot: u32, generation: u32) -> TimerId {
+        TimerId(((generation as u64) << 32) | slot as u64)
+    }
+
+    pub fn slot(&self) -> u32 {
+        self.0 as u32
+    }
+
+    pub fn generation(&self) -> u32 {
+        (self.0 >> 32) as u32
+    }
+
+    /// 是否为尚未分配的空句柄
+    pub fn is_none(&self) -> bool {
+        self.0 == 0
+    }
+}
+
+enum TimerQueue {
+    Tree(RBTree<TreeKey, EventEntry>),
+    Wheel(TimerWheel),
+}
+
 pub struct Timer {
-    timer_queue: RBTree<TreeKey, EventEntry>,
-    time_maps: HashMap<u32, u64>,
+    timer_queue: TimerQueue,
+    time_maps: HashMap<u32, (TimerId, u64)>,
     time_id: u32,
+    generation: u32,
+    running: u32,
     time_max_id: u32,
 }
 
 #[derive(PartialEq, Eq)]
-struct TreeKey(u64, u32);
+pub struct TreeKey(pub u64, pub u32);
 
 impl Ord for TreeKey {
     fn cmp(&self, other: &Self) -> Ordering {
@@ -36,96 +103,192 @@ impl PartialOrd for TreeKey {
 
 impl Timer {
     pub fn new(time_max_id: u32) -> Timer {
+        Timer::with_backend(time_max_id, TimerBackend::Tree)
+    }
+
+    pub fn with_backend(time_max_id: u32, backend: TimerBackend) -> Timer {
+        let timer_queue = match backend {
+            TimerBackend::Tree => TimerQueue::Tree(RBTree::new()),
+            TimerBackend::Wheel { tick_us } => {
+                TimerQueue::Wheel(TimerWheel::new(tick_us, now_monotonic()))
+            }
+        };
         Timer {
-            timer_queue: RBTree::new(),
+            timer_queue: timer_queue,
             time_maps: HashMap::new(),
             time_id: 0,
+            generation: 0,
+            running: 0,
             time_max_id: time_max_id,
         }
     }
 
     /// 添加定时器, 非定时器, 通常是重复定时器结束后进行的调用
-    pub fn add_timer(&mut self, mut entry: EventEntry) -> u32 {
+    pub fn add_timer(&mut self, mut entry: EventEntry) -> io::Result<TimerId> {
         if entry.tick_step == 0 {
-            return 0;
+            return Err(io::Error::new(ErrorKind::InvalidInput, "timer tick_step is zero"));
+        }
+        let now = now_monotonic();
+        if entry.time_id.is_none() {
+            entry.time_id = self.calc_new_id()?;
+            entry.tick_us = now + entry.tick_step;
+        } else {
+            entry.tick_us = Timer::next_tick(&entry, now);
         }
-        if entry.time_id == 0 {
-            entry.time_id = self.calc_new_id();
+        Ok(self.insert_entry(entry))
+    }
+
+    /// 根据重复定时器的调度方式计算下次的到期时间
+    fn next_tick(entry: &EventEntry, now: u64) -> u64 {
+        let step = entry.tick_step;
+        let missed = match entry.timer_repeat {
+            TimerRepeat::FixedDelay => return now + step,
+            TimerRepeat::FixedRate(missed) => missed,
         };
-        let time_id = entry.time_id;
-        entry.tick_ms = now_micro() + entry.tick_step;
-        self.time_maps.insert(time_id, entry.tick_ms);
-        self.timer_queue.insert(
-            TreeKey(entry.tick_ms, time_id),
-            entry,
-        );
-        time_id
+        let next = entry.tick_us + step;
+        if next > now {
+            return next;
+        }
+        let passed = (now - entry.tick_us) / step;
+        match missed {
+            MissedTick::CatchUp => next,
+            MissedTick::Skip => entry.tick_us + step * (passed + 1),
+            MissedTick::Coalesce => entry.tick_us + step * passed,
+        }
     }
 
     /// 添加首次的定时器, 不用step校验, 如果是重复定时器, 则第二次添加到定时器被检验
-    pub fn add_first_timer(&mut self, mut entry: EventEntry) -> u32 {
-        entry.time_id = self.calc_new_id();
-        let time_id = entry.time_id;
-        self.time_maps.insert(time_id, entry.tick_ms);
-        self.timer_queue.insert(
-            TreeKey(entry.tick_ms, time_id),
-            entry,
-        );
-        time_id
+    pub fn add_first_timer(&mut self, mut entry: EventEntry) -> io::Result<TimerId> {
+        entry.time_id = self.calc_new_id()?;
+        Ok(self.insert_entry(entry))
+    }
+
+    /// 根据定时器的id删除指定的定时器, 已失效的句柄不做任何操作
+    pub fn del_timer(&mut self, time_id: TimerId) -> Option<EventEntry> {
+        let tick_us = self.find(time_id)?;
+        let slot = time_id.slot();
+        self.time_maps.remove(&slot);
+        match self.timer_queue {
+            TimerQueue::Tree(ref mut tree) => tree.remove(&TreeKey(tick_us, slot)),
+            TimerQueue::Wheel(ref mut wheel) => wheel.remove(slot),
+        }
+    }
+
+    /// 重新设置定时器的间隔, 并从当前时间起重新计时, 定时器id保持不变
+    pub fn reset_timer(&mut self, time_id: TimerId, tick_step: u64) -> bool {
+        match self.del_timer(time_id) {
+            Some(mut entry) => {
+                entry.tick_step = tick_step;
+                entry.tick_us = now_monotonic() + tick_step;
+                self.insert_entry(entry);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// 把定时器的到期时间修改为指定的单调时钟时间, 定时器id保持不变
+    pub fn reschedule_timer(&mut self, time_id: TimerId, tick_us: u64) -> bool {
+        match self.del_timer(time_id) {
+            Some(mut entry) => {
+                entry.tick_us = tick_us;
+                self.insert_entry(entry);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// 定时器距离到期还剩余的微秒数, 已到期则为0
+    pub fn timer_remaining(&self, time_id: TimerId) -> Option<u64> {
+        let now = now_monotonic();
+        self.find(time_id)
+            .map(|tick_us| if tick_us > now { tick_us - now } else { 0 })
+    }
+
+    /// 判断定时器是否存在
+    pub fn has_timer(&self, time_id: TimerId) -> bool {
+        self.find(time_id).is_some()
     }
 
-    /// 根据定时器的id删除指定的定时器
-    pub fn del_timer(&mut self, time_id: u32) -> Option<EventEntry> {
-        if !self.time_maps.contains_key(&time_id) {
-            return None;
+    /// 查找定时器的到期时间, 槽位上的代数不一致则表示句柄已失效
+    fn find(&self, time_id: TimerId) -> Option<u64> {
+        match self.time_maps.get(&time_id.slot()) {
+            Some(&(id, tick_us)) if id == time_id => Some(tick_us),
+            _ => None,
         }
-        let key = TreeKey(self.time_maps[&time_id], time_id);
-        self.time_maps.remove(&time_id);
-        self.timer_queue.remove(&key)
     }
 
-    /// 取出时间轴最小的一个值
+    /// 取出时间轴最小的一个值, 时间轮模式下为最小值的下界
     pub fn tick_first(&self) -> Option<u64> {
-        self.timer_queue
-            .get_first()
-            .map(|(key, _)| Some(key.0))
-            .unwrap_or(None)
+        match self.timer_queue {
+            TimerQueue::Tree(ref tree) => tree.get_first().map(|(key, _)| key.0),
+            TimerQueue::Wheel(ref wheel) => wheel.first(),
+        }
     }
 
     /// 判断到指定时间是否有小于该指定值的实例
     pub fn tick_time(&mut self, tm: u64) -> Option<EventEntry> {
-        if tm < self.tick_first().unwrap_or(tm + 1) {
-            return None;
+        let entry = match self.timer_queue {
+            TimerQueue::Tree(ref mut tree) => {
+                if tm < tree.get_first().map(|(key, _)| key.0).unwrap_or(tm + 1) {
+                    return None;
+                }
+                tree.pop_first().map(|(_, entry)| entry)
+            }
+            TimerQueue::Wheel(ref mut wheel) => wheel.pop(tm),
+        };
+        if let Some(ref entry) = entry {
+            let slot = entry.time_id.slot();
+            self.time_maps.remove(&slot);
+            // 回调期间保留该槽位, 以便重复定时器以原来的id重新加入
+            self.running = slot;
         }
-        if let Some((key, entry)) = self.timer_queue.pop_first() {
-            self.time_maps.remove(&key.1);
-            Some(entry)
-        } else {
-            None
+        entry
+    }
+
+    fn insert_entry(&mut self, entry: EventEntry) -> TimerId {
+        let time_id = entry.time_id;
+        let slot = time_id.slot();
+        if self.running == slot {
+            self.running = 0;
         }
+        self.time_maps.insert(slot, (time_id, entry.tick_us));
+        match self.timer_queue {
+            TimerQueue::Tree(ref mut tree) => tree.insert(TreeKey(entry.tick_us, slot), entry),
+            TimerQueue::Wheel(ref mut wheel) => wheel.insert(entry),
+        }
+        time_id
     }
 
     /// 取出不冲突新的定时器id, 如果和已分配的定时器id重复则继续寻找下一个
-    fn calc_new_id(&mut self) -> u32 {
-        loop {
+    /// 槽位id回绕时代数加一, 所有槽位都已被占用则返回错误
+    fn calc_new_id(&mut self) -> io::Result<TimerId> {
+        for _ in 0..self.time_max_id {
             self.time_id = self.time_id.overflowing_add(1).0;
-            if self.time_id > self.time_max_id {
+            if self.time_id > self.time_max_id || self.time_id == 0 {
                 self.time_id = 1;
+                self.generation = self.generation.wrapping_add(1);
             }
-            if self.time_maps.contains_key(&self.time_id) {
+            if self.time_maps.contains_key(&self.time_id) || self.time_id == self.running {
                 continue;
             }
-            break;
+            return Ok(TimerId::new(self.time_id, self.generation));
         }
-        self.time_id
+        Err(io::Error::new(ErrorKind::Other, "no timer id available"))
     }
 }
 
 impl fmt::Debug for Timer {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        for (_, entry) in self.timer_queue.iter() {
-            let _ = writeln!(f, "{:?}", entry);
+        match self.timer_queue {
+            TimerQueue::Tree(ref tree) => {
+                for (_, entry) in tree.iter() {
+                    let _ = writeln!(f, "{:?}", entry);
+                }
+                write!(f, "")
+            }
+            TimerQueue::Wheel(ref wheel) => write!(f, "{:?}", wheel),
         }
-        write!(f, "")
     }
 }
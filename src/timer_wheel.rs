@@ -0,0 +1,270 @@
+use EventEntry;
+use timer::TreeKey;
+use std::fmt;
+use std::cmp;
+use std::mem;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use TimerId;
+use rbtree::RBTree;
+
+const WHEEL_BITS: u64 = 6;
+const WHEEL_SIZE: usize = 1 << WHEEL_BITS;
+const WHEEL_MASK: u64 = (WHEEL_SIZE - 1) as u64;
+const WHEEL_LEVELS: usize = 4;
+
+/// 定时器当前所在的位置, 删除时据此直接定位
+#[derive(Clone, Copy)]
+enum Location {
+    Ready,
+    Slot(usize, usize),
+    Overflow(u64),
+}
+
+/// 分层时间轮, 共WHEEL_LEVELS层, 每层WHEEL_SIZE个槽位, 第n层每个槽位覆盖64^n格
+/// 增删定时器均为O(1), 超出时间轮范围的定时器存放在红黑树中, 随时间推进再移入时间轮
+pub struct TimerWheel {
+    tick_us: u64,
+    current: u64,
+    slots: Vec<Vec<HashMap<u32, EventEntry>>>,
+    counts: [usize; WHEEL_LEVELS],
+    overflow: RBTree<TreeKey, EventEntry>,
+    /// 已到期的定时器, 按(到期时间, 定时器id)排列的最小堆, 删除时只从ready_entries中移除
+    /// 堆中失效的项在到达堆顶时丢弃, 堆顶始终为有效的定时器
+    ready: BinaryHeap<Reverse<(u64, TimerId)>>,
+    ready_entries: HashMap<u32, EventEntry>,
+    locations: HashMap<u32, Location>,
+}
+
+impl TimerWheel {
+    /// tick_us为每格的时间粒度, now为当前单调时钟的时间
+    pub fn new(tick_us: u64, now: u64) -> TimerWheel {
+        let tick_us = cmp::max(tick_us, 1);
+        TimerWheel {
+            tick_us: tick_us,
+            current: now / tick_us,
+            slots: (0..WHEEL_LEVELS)
+                .map(|_| (0..WHEEL_SIZE).map(|_| HashMap::new()).collect())
+                .collect(),
+            counts: [0; WHEEL_LEVELS],
+            overflow: RBTree::new(),
+            ready: BinaryHeap::new(),
+            ready_entries: HashMap::new(),
+            locations: HashMap::new(),
+        }
+    }
+
+    /// 按到期时间把定时器放入对应的层级及槽位
+    pub fn insert(&mut self, entry: EventEntry) {
+        let time_id = entry.time_id.slot();
+        let expire = entry.tick_us / self.tick_us;
+        let location = if expire < self.current {
+            self.push_ready(entry);
+            Location::Ready
+        } else {
+            let delta = expire - self.current;
+            let mut level = 0;
+            while level < WHEEL_LEVELS && delta >> (WHEEL_BITS * (level as u64 + 1)) != 0 {
+                level += 1;
+            }
+            if level == WHEEL_LEVELS {
+                let tick_us = entry.tick_us;
+                self.overflow.insert(TreeKey(tick_us, time_id), entry);
+                Location::Overflow(tick_us)
+            } else {
+                let idx = ((expire >> (WHEEL_BITS * level as u64)) & WHEEL_MASK) as usize;
+                self.slots[level][idx].insert(time_id, entry);
+                self.counts[level] += 1;
+                Location::Slot(level, idx)
+            }
+        };
+        self.locations.insert(time_id, location);
+    }
+
+    /// 根据定时器的槽位id删除指定的定时器
+    pub fn remove(&mut self, time_id: u32) -> Option<EventEntry> {
+        match self.locations.remove(&time_id)? {
+            Location::Ready => {
+                let entry = self.ready_entries.remove(&time_id);
+                self.purge_ready();
+                entry
+            }
+            Location::Slot(level, idx) => {
+                let entry = self.slots[level][idx].remove(&time_id);
+                if entry.is_some() {
+                    self.counts[level] -= 1;
+                }
+                entry
+            }
+            Location::Overflow(tick_us) => self.overflow.remove(&TreeKey(tick_us, time_id)),
+        }
+    }
+
+    /// 最早到期时间的下界, 第0层及红黑树为精确值, 更高层以槽位的起始时间作为下界
+    /// 主循环在该时间醒来后推进时间轮, 高层槽位展开后即可得到精确值
+    pub fn first(&self) -> Option<u64> {
+        if let Some(&Reverse((tick_us, _))) = self.ready.peek() {
+            return Some(tick_us);
+        }
+        let mut first = self.overflow.get_first().map(|(key, _)| key.0);
+        if self.counts[0] != 0 {
+            for i in 0..WHEEL_SIZE as u64 {
+                let idx = ((self.current + i) & WHEEL_MASK) as usize;
+                if let Some(tick_us) = self.slots[0][idx].values().map(|e| e.tick_us).min() {
+                    first = Some(first.map_or(tick_us, |f| cmp::min(f, tick_us)));
+                    break;
+                }
+            }
+        }
+        for level in 1..WHEEL_LEVELS {
+            if self.counts[level] == 0 {
+                continue;
+            }
+            let bits = WHEEL_BITS * level as u64;
+            let base = self.current >> bits;
+            for i in 1..(WHEEL_SIZE as u64 + 1) {
+                let block = base + i;
+                if !self.slots[level][(block & WHEEL_MASK) as usize].is_empty() {
+                    let tick_us = (block << bits) * self.tick_us;
+                    first = Some(first.map_or(tick_us, |f| cmp::min(f, tick_us)));
+                    break;
+                }
+            }
+        }
+        first
+    }
+
+    /// 推进时间轮到now, 并取出一个已到期的定时器
+    pub fn pop(&mut self, now: u64) -> Option<EventEntry> {
+        self.advance(now);
+        match self.ready.peek() {
+            Some(&Reverse((tick_us, _))) if tick_us <= now => (),
+            _ => return None,
+        }
+        let Reverse((_, time_id)) = self.ready.pop()?;
+        let entry = self.ready_entries.remove(&time_id.slot());
+        self.locations.remove(&time_id.slot());
+        self.purge_ready();
+        entry
+    }
+
+    fn push_ready(&mut self, entry: EventEntry) {
+        self.ready.push(Reverse((entry.tick_us, entry.time_id)));
+        self.ready_entries.insert(entry.time_id.slot(), entry);
+    }
+
+    /// 丢弃堆顶已被删除或重新加入的项, 失效的项过多时重建该堆
+    fn purge_ready(&mut self) {
+        if self.ready.len() > 64 && self.ready.len() > self.ready_entries.len() * 2 {
+            let ready = self.ready_entries
+                .values()
+                .map(|e| Reverse((e.tick_us, e.time_id)))
+                .collect::<Vec<_>>();
+            self.ready = BinaryHeap::from(ready);
+            return;
+        }
+        while let Some(&Reverse((tick_us, time_id))) = self.ready.peek() {
+            let is_valid = self.ready_entries
+                .get(&time_id.slot())
+                .map_or(false, |e| e.time_id == time_id && e.tick_us == tick_us);
+            if is_valid {
+                break;
+            }
+            self.ready.pop();
+        }
+    }
+
+    fn advance(&mut self, now: u64) {
+        let target = now / self.tick_us;
+        while self.current < target {
+            if self.counts.iter().all(|&count| count == 0) && self.overflow.is_empty() {
+                self.current = target;
+                break;
+            }
+            // 低层的槽位全部为空时, 直接跳到下一个需要展开高层槽位的位置
+            let mut level = 0;
+            while level < WHEEL_LEVELS - 1 && self.counts[level] == 0 {
+                level += 1;
+            }
+            if level > 0 {
+                let bits = WHEEL_BITS * level as u64;
+                let next = ((self.current >> bits) + 1) << bits;
+                self.current = cmp::min(next, target);
+                self.cascade();
+                continue;
+            }
+
+            let idx = (self.current & WHEEL_MASK) as usize;
+            let slot = mem::replace(&mut self.slots[0][idx], HashMap::new());
+            self.counts[0] -= slot.len();
+            for (_, entry) in slot {
+                self.locations.insert(entry.time_id.slot(), Location::Ready);
+                self.push_ready(entry);
+            }
+            self.current += 1;
+            self.cascade();
+        }
+
+        // 当前格内已到期的定时器
+        let idx = (self.current & WHEEL_MASK) as usize;
+        if !self.slots[0][idx].is_empty() {
+            let due: Vec<u32> = self.slots[0][idx]
+                .iter()
+                .filter(|&(_, e)| e.tick_us <= now)
+                .map(|(time_id, _)| *time_id)
+                .collect();
+            for time_id in due {
+                let entry = self.slots[0][idx].remove(&time_id).unwrap();
+                self.counts[0] -= 1;
+                self.locations.insert(time_id, Location::Ready);
+                self.push_ready(entry);
+            }
+        }
+    }
+
+    /// 到达高层槽位的边界时, 把该槽位的定时器重新放入低层, 并把进入范围的红黑树定时器移入时间轮
+    fn cascade(&mut self) {
+        if self.current & WHEEL_MASK != 0 {
+            return;
+        }
+        let range = 1u64 << (WHEEL_BITS * WHEEL_LEVELS as u64);
+        while self.overflow
+            .get_first()
+            .map_or(false, |(key, _)| key.0 / self.tick_us < self.current + range)
+        {
+            let (_, entry) = self.overflow.pop_first().unwrap();
+            self.insert(entry);
+        }
+        for level in 1..WHEEL_LEVELS {
+            let bits = WHEEL_BITS * level as u64;
+            if self.current & ((1 << bits) - 1) != 0 {
+                break;
+            }
+            let idx = ((self.current >> bits) & WHEEL_MASK) as usize;
+            let slot = mem::replace(&mut self.slots[level][idx], HashMap::new());
+            self.counts[level] -= slot.len();
+            for (_, entry) in slot {
+                self.insert(entry);
+            }
+        }
+    }
+}
+
+impl fmt::Debug for TimerWheel {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for entry in self.ready_entries.values() {
+            let _ = writeln!(f, "{:?}", entry);
+        }
+        for level in self.slots.iter() {
+            for slot in level.iter() {
+                for entry in slot.values() {
+                    let _ = writeln!(f, "{:?}", entry);
+                }
+            }
+        }
+        for (_, entry) in self.overflow.iter() {
+            let _ = writeln!(f, "{:?}", entry);
+        }
+        write!(f, "")
+    }
+}
@@ -0,0 +1,54 @@
+extern crate td_revent;
+extern crate psocket;
+
+use td_revent::*;
+use std::net::TcpStream;
+use std::thread;
+use std::rc::Rc;
+use std::cell::Cell;
+use self::psocket::TcpSocket;
+
+/// 在addr上监听, 接受的连接设为非阻塞后以entry()生成的EventEntry注册, 客户端在线程中连接后执行client
+/// 连接结束时先调用entry中的end回调, 再记录结束原因并退出主循环
+/// 主循环退出后关闭所有socket并等待客户端完成, 返回连接的结束原因
+pub fn run_server<E, C>(addr: &'static str, mut entry: E, client: C) -> Option<EndReason>
+where
+    E: FnMut() -> EventEntry + 'static,
+    C: FnOnce(TcpStream) + Send + 'static,
+{
+    let mut event_loop = EventLoop::new().unwrap();
+
+    let listener = TcpSocket::bind(&addr).unwrap();
+    let _ = listener.set_nonblocking(true);
+
+    let reason = Rc::new(Cell::new(None));
+    let reason_clone = reason.clone();
+    let _ = event_loop.add_new_accept_fn(
+        listener,
+        EventFlags::FLAG_READ | EventFlags::FLAG_PERSIST | EventFlags::FLAG_ACCEPT,
+        Some(Box::new(move |ev, tcp| {
+            let reason = reason_clone.clone();
+            let mut entry = entry();
+            let mut end = entry.end_fn.take();
+            entry.end_fn = Some(Box::new(move |ev, buffer| {
+                if let Some(ref mut end) = end {
+                    end(ev, buffer);
+                }
+                reason.set(buffer.end_reason);
+                ev.shutdown();
+            }));
+            let tcp = tcp.unwrap();
+            tcp.set_nonblocking(true).unwrap();
+            let buffer = ev.new_buff(tcp);
+            ev.register_socket(buffer, entry).unwrap();
+            RetValue::OK
+        })),
+        None,
+    );
+
+    let client = thread::spawn(move || client(TcpStream::connect(addr).unwrap()));
+    event_loop.run().unwrap();
+    drop(event_loop);
+    client.join().unwrap();
+    reason.get()
+}
@@ -3,3 +3,38 @@ extern crate td_revent;
 
 mod test_timer;
 mod test_base_echo;
+mod test_closure;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+mod support;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+mod test_handle;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+mod test_signal;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+mod test_timeout;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+mod test_connect;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+mod test_udp;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+mod test_unix;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+mod test_fd;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+mod test_process;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+mod test_edge;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+mod test_oneshot;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+mod test_hangup;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+mod test_half_close;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+mod test_flush;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+mod test_watermark;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+mod test_pause;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+mod test_read_limit;
@@ -0,0 +1,81 @@
+extern crate td_revent;
+extern crate psocket;
+
+use td_revent::*;
+use std::io::prelude::*;
+use std::rc::Rc;
+use std::cell::Cell;
+use self::psocket::TcpSocket;
+
+#[test]
+fn test_closure_timer() {
+    let mut event_loop = EventLoop::new().unwrap();
+    let count = Rc::new(Cell::new(0));
+    let count_clone = count.clone();
+    event_loop.add_new_timer_fn(10_000, true, Box::new(move |ev, _timer| {
+        count_clone.set(count_clone.get() + 1);
+        if count_clone.get() >= 3 {
+            ev.shutdown();
+            return (RetValue::OVER, 0);
+        }
+        (RetValue::OK, 0)
+    })).unwrap();
+    event_loop.run().unwrap();
+    assert!(count.get() == 3);
+}
+
+#[test]
+fn test_closure_echo() {
+    let mut event_loop = EventLoop::new().unwrap();
+
+    let addr = "127.0.0.1:10010";
+    let listener = TcpSocket::bind(&addr).unwrap();
+    let _ = listener.set_nonblocking(true);
+
+    let mut client = TcpSocket::connect(&addr).unwrap();
+    let _ = client.set_nonblocking(true);
+    client.write(b"hello world. ").unwrap();
+
+    let _ = event_loop.add_new_accept_fn(
+        listener,
+        EventFlags::FLAG_READ | EventFlags::FLAG_PERSIST | EventFlags::FLAG_ACCEPT,
+        Some(Box::new(|ev, tcp| {
+            let _ = ev.add_new_event_fn(
+                tcp.unwrap(),
+                EventFlags::FLAG_READ | EventFlags::FLAG_PERSIST,
+                Some(Box::new(|ev, buffer| {
+                    let len = buffer.read.len();
+                    let data = buffer.read.drain_collect(len);
+                    let _ = ev.send_socket(&buffer.as_raw_socket(), &data[..]);
+                    RetValue::OK
+                })),
+                None,
+                Some(Box::new(|ev, _buffer| ev.shutdown())),
+            );
+            RetValue::OK
+        })),
+        None,
+    );
+
+    let count = Rc::new(Cell::new(0));
+    let count_clone = count.clone();
+    let _ = event_loop.add_new_event_fn(
+        client,
+        EventFlags::FLAG_READ | EventFlags::FLAG_PERSIST,
+        Some(Box::new(move |ev, buffer| {
+            let len = buffer.read.len();
+            let data = buffer.read.drain_collect(len);
+            count_clone.set(count_clone.get() + 1);
+            if count_clone.get() >= 6 {
+                return RetValue::OVER;
+            }
+            let _ = ev.send_socket(&buffer.as_raw_socket(), &data[..]);
+            RetValue::OK
+        })),
+        None,
+        None,
+    );
+
+    event_loop.run().unwrap();
+    assert!(count.get() == 6);
+}
@@ -0,0 +1,89 @@
+extern crate td_revent;
+extern crate psocket;
+
+use td_revent::*;
+use std::io::ErrorKind;
+use std::rc::Rc;
+use std::cell::Cell;
+use self::psocket::{TcpSocket, ToSocketAddrs};
+
+#[test]
+fn test_connect() {
+    let mut event_loop = EventLoop::new().unwrap();
+
+    let addr = "127.0.0.1:10013";
+    let listener = TcpSocket::bind(&addr).unwrap();
+    let _ = listener.set_nonblocking(true);
+
+    let _ = event_loop.add_new_accept_fn(
+        listener,
+        EventFlags::FLAG_READ | EventFlags::FLAG_PERSIST | EventFlags::FLAG_ACCEPT,
+        Some(Box::new(|ev, tcp| {
+            let _ = ev.add_new_event_fn(
+                tcp.unwrap(),
+                EventFlags::FLAG_READ | EventFlags::FLAG_PERSIST,
+                Some(Box::new(|ev, buffer| {
+                    let data = buffer.read.drain_all_collect();
+                    let _ = ev.send_socket(&buffer.as_raw_socket(), &data[..]);
+                    RetValue::OK
+                })),
+                None,
+                None,
+            );
+            RetValue::OK
+        })),
+        None,
+    );
+
+    let connected = Rc::new(Cell::new(false));
+    let connected_clone = connected.clone();
+    let received = Rc::new(Cell::new(false));
+    let received_clone = received.clone();
+    let addr = addr.to_socket_addrs().unwrap().next().unwrap();
+    let socket = event_loop.connect_fn(
+        &addr,
+        1_000_000,
+        Some(Box::new(move |ev, buffer| {
+            assert!(buffer.error.is_ok());
+            connected_clone.set(true);
+            let _ = ev.send_socket(&buffer.as_raw_socket(), b"hello");
+            RetValue::OK
+        })),
+        Some(Box::new(move |ev, buffer| {
+            assert_eq!(buffer.read.drain_all_collect(), b"hello");
+            received_clone.set(true);
+            ev.shutdown();
+            RetValue::OVER
+        })),
+        None,
+    );
+    assert!(socket.is_ok());
+
+    event_loop.run().unwrap();
+    assert!(connected.get());
+    assert!(received.get());
+}
+
+#[test]
+fn test_connect_refused() {
+    let mut event_loop = EventLoop::new().unwrap();
+
+    // 该端口没有监听, 连接将被拒绝
+    let addr = "127.0.0.1:10014".to_socket_addrs().unwrap().next().unwrap();
+    let reason = Rc::new(Cell::new(None));
+    let reason_clone = reason.clone();
+    let _ = event_loop.connect_fn(
+        &addr,
+        1_000_000,
+        Some(Box::new(move |ev, buffer| {
+            reason_clone.set(buffer.error.as_ref().err().map(|e| e.kind()));
+            ev.shutdown();
+            RetValue::OK
+        })),
+        None,
+        None,
+    );
+
+    event_loop.run().unwrap();
+    assert_eq!(reason.get(), Some(ErrorKind::ConnectionRefused));
+}
@@ -0,0 +1,58 @@
+extern crate td_revent;
+extern crate psocket;
+
+use td_revent::*;
+use std::io::prelude::*;
+use std::net::TcpStream;
+use std::thread;
+use self::psocket::TcpSocket;
+
+const DATA_LEN: usize = 1024 * 1024;
+
+#[test]
+fn test_edge_echo() {
+    let addr = "127.0.0.1:10017";
+    let mut event_loop = EventLoop::new().unwrap();
+
+    let listener = TcpSocket::bind(&addr).unwrap();
+    let _ = listener.set_nonblocking(true);
+    let _ = event_loop.add_new_accept_fn(
+        listener,
+        EventFlags::FLAG_READ | EventFlags::FLAG_PERSIST | EventFlags::FLAG_ACCEPT | EventFlags::FLAG_EDGE,
+        Some(Box::new(|ev, tcp| {
+            let entry = EventEntry::new_event_fn(
+                0,
+                EventFlags::FLAG_READ | EventFlags::FLAG_PERSIST | EventFlags::FLAG_EDGE,
+                Some(Box::new(|ev, buffer| {
+                    let data = buffer.read.drain_all_collect();
+                    let _ = ev.send_socket(&buffer.as_raw_socket(), &data[..]);
+                    RetValue::OK
+                })),
+                None,
+                Some(Box::new(|ev, _| ev.shutdown())),
+            );
+            let buffer = ev.new_buff(tcp.unwrap());
+            let _ = ev.register_socket(buffer, entry);
+            RetValue::OK
+        })),
+        None,
+    );
+
+    // 一次写入大量数据, 边缘触发模式下须读写到EAGAIN才能完整回显
+    let client = thread::spawn(move || {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        let mut reader = stream.try_clone().unwrap();
+        let reader = thread::spawn(move || {
+            let mut data = vec![0; DATA_LEN];
+            reader.read_exact(&mut data[..]).unwrap();
+            data
+        });
+        let data: Vec<u8> = (0..DATA_LEN).map(|i| i as u8).collect();
+        stream.write_all(&data[..]).unwrap();
+        let echo = reader.join().unwrap();
+        assert!(echo == data);
+    });
+
+    event_loop.run().unwrap();
+    client.join().unwrap();
+}
@@ -0,0 +1,65 @@
+extern crate td_revent;
+extern crate libc;
+
+use td_revent::*;
+use std::fs::File;
+use std::io::prelude::*;
+use std::os::unix::io::{AsRawFd, FromRawFd};
+use std::rc::Rc;
+use std::cell::Cell;
+
+fn pipe() -> (File, File) {
+    let mut fds = [0; 2];
+    assert_eq!(unsafe { libc::pipe(fds.as_mut_ptr()) }, 0);
+    unsafe { (File::from_raw_fd(fds[0]), File::from_raw_fd(fds[1])) }
+}
+
+#[test]
+fn test_register_fd() {
+    let mut event_loop = EventLoop::new().unwrap();
+    let (mut reader, mut writer) = pipe();
+    writer.write(b"hello").unwrap();
+
+    let count = Rc::new(Cell::new(0));
+    let count_clone = count.clone();
+    let fd = reader.as_raw_fd();
+    event_loop.register_fd_fn(fd, EventFlags::FLAG_READ, Box::new(move |ev, _fd, events| {
+        assert!(events.contains(EventFlags::FLAG_READ));
+        let mut buf = [0; 16];
+        let len = reader.read(&mut buf).unwrap();
+        assert_eq!(&buf[..len], b"hello");
+        count_clone.set(count_clone.get() + 1);
+        ev.shutdown();
+        RetValue::OVER
+    })).unwrap();
+
+    event_loop.run().unwrap();
+    assert_eq!(count.get(), 1);
+}
+
+#[test]
+fn test_buffered_fd() {
+    let mut event_loop = EventLoop::new().unwrap();
+    let (reader, mut writer) = pipe();
+    writer.write(b"hello").unwrap();
+    drop(writer);
+
+    let data = Rc::new(Cell::new(Vec::new()));
+    let data_clone = data.clone();
+    event_loop.add_new_event_fn(
+        reader,
+        EventFlags::FLAG_READ | EventFlags::FLAG_PERSIST,
+        Some(Box::new(move |_, buffer| {
+            let mut read = data_clone.take();
+            read.extend(buffer.read.drain_all_collect());
+            data_clone.set(read);
+            RetValue::OK
+        })),
+        None,
+        // 写端关闭后读到EOF, 结束回调
+        Some(Box::new(|ev, _| ev.shutdown())),
+    ).unwrap();
+
+    event_loop.run().unwrap();
+    assert_eq!(data.take(), b"hello");
+}
@@ -0,0 +1,54 @@
+extern crate td_revent;
+
+use td_revent::*;
+use std::io::prelude::*;
+use std::thread;
+use std::time::Duration;
+use support::run_server;
+
+const DATA_LEN: usize = 4 * 1024 * 1024;
+
+/// 客户端发送请求后, 服务端回复大量数据并在回调中返回FLUSH, 或者调用close_after_flush
+fn run_flush_server(addr: &'static str, timeout: Option<u64>) -> Option<EndReason> {
+    run_server(addr, move || {
+        EventEntry::new_event_fn(
+            0,
+            EventFlags::FLAG_READ | EventFlags::FLAG_PERSIST,
+            Some(Box::new(move |ev, buffer| {
+                buffer.read.drain_all();
+                let socket = buffer.as_raw_socket();
+                ev.send_socket(&socket, &vec![1; DATA_LEN][..]).unwrap();
+                match timeout {
+                    Some(timeout) => {
+                        ev.close_after_flush(socket, timeout).unwrap();
+                        assert!(ev.send_socket(&socket, b"more").is_err());
+                        RetValue::OK
+                    }
+                    None => RetValue::FLUSH,
+                }
+            })),
+            None,
+            None,
+        )
+    }, move |mut stream| {
+        stream.write_all(b"request").unwrap();
+        if timeout.is_some() {
+            // 不读取数据, 等待服务端超时
+            thread::sleep(Duration::from_millis(200));
+            return;
+        }
+        let mut data = Vec::new();
+        stream.read_to_end(&mut data).unwrap();
+        assert_eq!(data.len(), DATA_LEN);
+    })
+}
+
+#[test]
+fn test_flush_ret_value() {
+    assert_eq!(run_flush_server("127.0.0.1:10023", None), Some(EndReason::Flushed));
+}
+
+#[test]
+fn test_flush_timeout() {
+    assert_eq!(run_flush_server("127.0.0.1:10024", Some(50_000)), Some(EndReason::Timeout));
+}
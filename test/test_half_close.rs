@@ -0,0 +1,38 @@
+extern crate td_revent;
+
+use td_revent::*;
+use std::io::prelude::*;
+use std::net::Shutdown;
+use support::run_server;
+
+#[test]
+fn test_half_close() {
+    let reason = run_server("127.0.0.1:10022", || {
+        EventEntry::new_event_fn(
+            0,
+            EventFlags::FLAG_READ | EventFlags::FLAG_PERSIST,
+            Some(Box::new(|ev, buffer| {
+                let data = buffer.read.drain_all_collect();
+                let _ = ev.send_socket(&buffer.as_raw_socket(), &data[..]);
+                RetValue::OK
+            })),
+            None,
+            None,
+        ).with_read_eof_fn(Box::new(|ev, buffer| {
+            // 对端已关闭写端, 仍可继续发送数据
+            let socket = buffer.as_raw_socket();
+            ev.send_socket(&socket, b" bye").unwrap();
+            ev.shutdown_write(socket).unwrap();
+            assert!(ev.send_socket(&socket, b"more").is_err());
+            RetValue::OK
+        }))
+    }, |mut stream| {
+        stream.write_all(b"hello").unwrap();
+        stream.shutdown(Shutdown::Write).unwrap();
+        let mut data = Vec::new();
+        stream.read_to_end(&mut data).unwrap();
+        assert_eq!(&data[..], b"hello bye");
+    });
+
+    assert_eq!(reason, Some(EndReason::Eof));
+}
@@ -0,0 +1,26 @@
+use td_revent::*;
+use std::thread;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+#[test]
+fn test_handle_post_and_shutdown() {
+    let mut event_loop = EventLoop::new().unwrap();
+    let handle = event_loop.handle();
+    let count = Arc::new(AtomicUsize::new(0));
+    let count_clone = count.clone();
+
+    let worker = thread::spawn(move || {
+        for _ in 0..5 {
+            let count = count_clone.clone();
+            handle.post(move |_ev| {
+                count.fetch_add(1, Ordering::SeqCst);
+            }).unwrap();
+        }
+        handle.shutdown().unwrap();
+    });
+
+    event_loop.run().unwrap();
+    worker.join().unwrap();
+    assert!(count.load(Ordering::SeqCst) == 5);
+}
@@ -0,0 +1,62 @@
+extern crate td_revent;
+extern crate libc;
+
+use td_revent::*;
+use std::io::prelude::*;
+use std::io::ErrorKind;
+use std::mem;
+use std::os::unix::io::AsRawFd;
+use std::thread;
+use std::time::Duration;
+use std::rc::Rc;
+use std::cell::Cell;
+use support::run_server;
+
+/// 客户端连接并发送数据后关闭, reset为true时以RST关闭, 返回服务端结束时的end_flags, 结束原因及错误
+fn run_hangup_server(addr: &'static str, reset: bool) -> (EventFlags, Option<EndReason>, Option<ErrorKind>) {
+    let result = Rc::new(Cell::new((EventFlags::empty(), None)));
+    let result_clone = result.clone();
+    let reason = run_server(addr, move || {
+        let result = result_clone.clone();
+        EventEntry::new_event_fn(
+            0,
+            EventFlags::FLAG_READ | EventFlags::FLAG_PERSIST,
+            None,
+            None,
+            Some(Box::new(move |_, buffer| {
+                result.set((buffer.end_flags, buffer.error.as_ref().err().map(|e| e.kind())));
+            })),
+        )
+    }, move |mut stream| {
+        stream.write_all(b"hello").unwrap();
+        if reset {
+            let linger = libc::linger { l_onoff: 1, l_linger: 0 };
+            unsafe {
+                libc::setsockopt(stream.as_raw_fd(), libc::SOL_SOCKET, libc::SO_LINGER,
+                    &linger as *const _ as *const libc::c_void, mem::size_of::<libc::linger>() as libc::socklen_t);
+            }
+        }
+        // 等待服务端接受连接后再关闭
+        thread::sleep(Duration::from_millis(50));
+    });
+
+    let (flags, err) = result.get();
+    (flags, reason, err)
+}
+
+#[test]
+fn test_peer_close() {
+    let (flags, reason, err) = run_hangup_server("127.0.0.1:10020", false);
+    assert!(flags.contains(EventFlags::FLAG_ENDED));
+    assert_eq!(reason, Some(EndReason::Eof));
+    assert!(!flags.contains(EventFlags::FLAG_ERROR));
+    assert_eq!(err, None);
+}
+
+#[test]
+fn test_peer_reset() {
+    let (flags, reason, err) = run_hangup_server("127.0.0.1:10021", true);
+    assert!(flags.contains(EventFlags::FLAG_ERROR));
+    assert_eq!(reason, Some(EndReason::SocketError));
+    assert_eq!(err, Some(ErrorKind::ConnectionReset));
+}
@@ -0,0 +1,75 @@
+extern crate td_revent;
+
+use td_revent::*;
+use std::io::prelude::*;
+use std::thread;
+use std::time::Duration;
+use std::rc::Rc;
+use std::cell::Cell;
+use support::run_server;
+
+/// 统计读写回调的次数, 读到"b"时退出主循环
+/// 读到其它数据时不再监听可读, 100毫秒后重新加入FLAG_READ
+fn oneshot_entry(ev_events: EventFlags, read: Rc<Cell<u32>>, write: Rc<Cell<u32>>) -> EventEntry {
+    EventEntry::new_event_fn(
+        0,
+        ev_events,
+        Some(Box::new(move |ev, buffer| {
+            let data = buffer.read.drain_all_collect();
+            read.set(read.get() + 1);
+            if &data[..] == b"b" {
+                ev.shutdown();
+                return RetValue::OK;
+            }
+            let socket = buffer.as_raw_socket();
+            let read = read.clone();
+            ev.add_new_timer_fn(100_000, false, Box::new(move |ev, _| {
+                assert_eq!(read.get(), 1);
+                let entry = EventEntry::new_event_fn(socket, EventFlags::FLAG_READ, None, None, None);
+                ev.modify_socket(false, socket, entry).unwrap();
+                (RetValue::OVER, 0)
+            })).unwrap();
+            RetValue::OK
+        })),
+        Some(Box::new(move |_, _| {
+            write.set(write.get() + 1);
+            RetValue::OK
+        })),
+        None,
+    )
+}
+
+#[test]
+fn test_oneshot_read() {
+    let read = Rc::new(Cell::new(0));
+    let read_clone = read.clone();
+    run_server("127.0.0.1:10018", move || {
+        oneshot_entry(EventFlags::FLAG_READ, read_clone.clone(), Rc::new(Cell::new(0)))
+    }, |mut stream| {
+        // 第一次读回调后不再监听可读, 之后到达的数据在重新加入FLAG_READ前不会回调
+        stream.write_all(b"a").unwrap();
+        thread::sleep(Duration::from_millis(50));
+        stream.write_all(b"b").unwrap();
+        let mut data = Vec::new();
+        stream.read_to_end(&mut data).unwrap();
+    });
+
+    assert_eq!(read.get(), 2);
+}
+
+#[test]
+fn test_oneshot_write() {
+    let write = Rc::new(Cell::new(0));
+    let write_clone = write.clone();
+    run_server("127.0.0.1:10019", move || {
+        oneshot_entry(EventFlags::FLAG_READ | EventFlags::FLAG_READ_PERSIST | EventFlags::FLAG_WRITE,
+            Rc::new(Cell::new(0)), write_clone.clone())
+    }, |mut stream| {
+        thread::sleep(Duration::from_millis(50));
+        stream.write_all(b"b").unwrap();
+        let mut data = Vec::new();
+        stream.read_to_end(&mut data).unwrap();
+    });
+
+    assert_eq!(write.get(), 1);
+}
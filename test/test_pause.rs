@@ -0,0 +1,52 @@
+extern crate td_revent;
+
+use td_revent::*;
+use std::io::prelude::*;
+use std::thread;
+use std::time::Duration;
+use std::rc::Rc;
+use std::cell::Cell;
+use support::run_server;
+
+#[test]
+fn test_pause_resume_read() {
+    let reads = Rc::new(Cell::new(0));
+    let reads_clone = reads.clone();
+    let reason = run_server("127.0.0.1:10026", move || {
+        let reads = reads_clone.clone();
+        EventEntry::new_event_fn(
+            0,
+            EventFlags::FLAG_READ | EventFlags::FLAG_PERSIST,
+            Some(Box::new(move |ev, buffer| {
+                let data = buffer.read.drain_all_collect();
+                let socket = buffer.as_raw_socket();
+                let _ = ev.send_socket(&socket, &data[..]);
+                reads.set(reads.get() + 1);
+                if reads.get() > 1 {
+                    return RetValue::FLUSH;
+                }
+                // 暂停期间对端的数据留在内核缓冲中, 读空闲超时也不会触发
+                ev.pause_read(socket).unwrap();
+                let reads = reads.clone();
+                let _ = ev.add_new_timer_fn(200_000, false, Box::new(move |ev, _| {
+                    assert_eq!(reads.get(), 1);
+                    ev.resume_read(socket).unwrap();
+                    (RetValue::OVER, 0)
+                }));
+                RetValue::OK
+            })),
+            None,
+            None,
+        ).with_timeouts(100_000, 0, 0)
+    }, |mut stream| {
+        stream.write_all(b"hello").unwrap();
+        thread::sleep(Duration::from_millis(50));
+        stream.write_all(b"world").unwrap();
+        let mut data = Vec::new();
+        stream.read_to_end(&mut data).unwrap();
+        assert_eq!(&data[..], b"helloworld");
+    });
+
+    assert_eq!(reads.get(), 2);
+    assert_eq!(reason, Some(EndReason::Flushed));
+}
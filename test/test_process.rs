@@ -0,0 +1,61 @@
+extern crate td_revent;
+extern crate libc;
+
+use td_revent::*;
+use std::process::Command;
+use std::os::unix::process::ExitStatusExt;
+use std::rc::Rc;
+use std::cell::{Cell, RefCell};
+
+#[test]
+fn test_spawn_cat() {
+    let mut event_loop = EventLoop::new().unwrap();
+    let output = Rc::new(RefCell::new(Vec::new()));
+    let output_clone = output.clone();
+    let code = Rc::new(Cell::new(None));
+    let code_clone = code.clone();
+
+    let mut command = Command::new("sh");
+    command.arg("-c").arg("cat; exit 3");
+    let child = event_loop.spawn(
+        command,
+        Some(Box::new(move |_, buffer| {
+            output_clone.borrow_mut().extend(buffer.read.drain_all_collect());
+            RetValue::OK
+        })),
+        None,
+        Some(Box::new(move |ev, _, status| {
+            code_clone.set(status.code());
+            ev.shutdown();
+        })),
+    ).unwrap();
+
+    event_loop.send_socket(&child.stdin, b"hello").unwrap();
+    // 数据写出后关闭stdin, cat读到EOF后退出
+    let stdin = child.stdin;
+    event_loop.add_new_timer_fn(10_000, false, Box::new(move |ev, _| {
+        let _ = ev.unregister_socket(stdin);
+        (RetValue::OVER, 0)
+    })).unwrap();
+    event_loop.run().unwrap();
+    assert_eq!(code.get(), Some(3));
+    assert_eq!(&output.borrow()[..], b"hello");
+}
+
+#[test]
+fn test_process_timeout() {
+    let mut event_loop = EventLoop::new().unwrap();
+    let signal = Rc::new(Cell::new(None));
+    let signal_clone = signal.clone();
+
+    let mut command = Command::new("sleep");
+    command.arg("10");
+    let child = event_loop.spawn(command, None, None, Some(Box::new(move |ev, _, status| {
+        signal_clone.set(status.signal());
+        ev.shutdown();
+    }))).unwrap();
+    event_loop.set_process_timeout(child.pid, 50_000).unwrap();
+
+    event_loop.run().unwrap();
+    assert_eq!(signal.get(), Some(libc::SIGKILL));
+}
@@ -0,0 +1,103 @@
+extern crate td_revent;
+
+use td_revent::*;
+use std::io::prelude::*;
+use std::thread;
+use std::time::Duration;
+use std::rc::Rc;
+use std::cell::Cell;
+use support::run_server;
+
+#[test]
+fn test_read_low() {
+    let reads = Rc::new(Cell::new(0));
+    let reads_clone = reads.clone();
+    let reason = run_server("127.0.0.1:10027", move || {
+        let reads = reads_clone.clone();
+        EventEntry::new_event_fn(
+            0,
+            EventFlags::FLAG_READ | EventFlags::FLAG_PERSIST,
+            Some(Box::new(move |ev, buffer| {
+                // 不足低水位时不回调, 收到完整的数据后才回调一次
+                reads.set(reads.get() + 1);
+                let data = buffer.read.drain_all_collect();
+                assert_eq!(&data[..], b"helloworld");
+                let _ = ev.send_socket(&buffer.as_raw_socket(), &data[..]);
+                RetValue::FLUSH
+            })),
+            None,
+            None,
+        ).with_read_low(10)
+    }, |mut stream| {
+        stream.write_all(b"hello").unwrap();
+        thread::sleep(Duration::from_millis(50));
+        stream.write_all(b"world").unwrap();
+        let mut data = Vec::new();
+        stream.read_to_end(&mut data).unwrap();
+        assert_eq!(&data[..], b"helloworld");
+    });
+
+    assert_eq!(reads.get(), 1);
+    assert_eq!(reason, Some(EndReason::Flushed));
+}
+
+#[test]
+fn test_read_overflow() {
+    let reason = run_server("127.0.0.1:10028", || {
+        EventEntry::new_event_fn(
+            0,
+            EventFlags::FLAG_READ | EventFlags::FLAG_PERSIST,
+            // 不取走数据, 读缓冲持续增长
+            Some(Box::new(|_, _| RetValue::OK)),
+            None,
+            Some(Box::new(|_, buffer| assert!(buffer.read.len() >= 8))),
+        ).with_read_limit(8, false)
+    }, |mut stream| {
+        stream.write_all(b"0123456789abcdef").unwrap();
+        let mut data = Vec::new();
+        let _ = stream.read_to_end(&mut data);
+        assert!(data.is_empty());
+    });
+
+    assert_eq!(reason, Some(EndReason::ReadOverflow));
+}
+
+#[test]
+fn test_read_limit_pause() {
+    let reads = Rc::new(Cell::new(0));
+    let reads_clone = reads.clone();
+    let reason = run_server("127.0.0.1:10030", move || {
+        let reads = reads_clone.clone();
+        EventEntry::new_event_fn(
+            0,
+            EventFlags::FLAG_READ | EventFlags::FLAG_PERSIST,
+            Some(Box::new(move |ev, buffer| {
+                reads.set(reads.get() + 1);
+                if reads.get() == 1 {
+                    // 不取走数据, 读缓冲达到上限后暂停读取, 稍后恢复
+                    let socket = buffer.as_raw_socket();
+                    let _ = ev.add_new_timer_fn(50_000, false, Box::new(move |ev, _| {
+                        ev.resume_read(socket).unwrap();
+                        (RetValue::OVER, 0)
+                    }));
+                    return RetValue::OK;
+                }
+                // 对端在等待回复, 不再发送数据, 恢复后由读缓冲中已有的数据回调
+                let data = buffer.read.drain_all_collect();
+                assert_eq!(&data[..], b"0123456789abcdef");
+                let _ = ev.send_socket(&buffer.as_raw_socket(), b"ok");
+                RetValue::FLUSH
+            })),
+            None,
+            None,
+        ).with_read_limit(8, true)
+    }, |mut stream| {
+        stream.write_all(b"0123456789abcdef").unwrap();
+        let mut data = Vec::new();
+        stream.read_to_end(&mut data).unwrap();
+        assert_eq!(&data[..], b"ok");
+    });
+
+    assert_eq!(reads.get(), 2);
+    assert_eq!(reason, Some(EndReason::Flushed));
+}
@@ -0,0 +1,24 @@
+extern crate libc;
+
+use td_revent::*;
+
+static mut S_SIGNO: i32 = 0;
+
+fn signal_callback(ev: &mut EventLoop, signo: i32, _data: Option<&mut CellAny>) -> RetValue {
+    unsafe {
+        S_SIGNO = signo;
+    }
+    ev.shutdown();
+    RetValue::OVER
+}
+
+#[test]
+fn test_signal() {
+    let mut event_loop = EventLoop::new().unwrap();
+    event_loop.add_signal(libc::SIGUSR1, Some(signal_callback), None).unwrap();
+    unsafe {
+        libc::raise(libc::SIGUSR1);
+    }
+    event_loop.run().unwrap();
+    assert!(unsafe { S_SIGNO } == libc::SIGUSR1);
+}
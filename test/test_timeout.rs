@@ -0,0 +1,65 @@
+extern crate td_revent;
+
+use td_revent::*;
+use std::io::prelude::*;
+use std::io::ErrorKind;
+use std::sync::mpsc;
+use std::rc::Rc;
+use std::cell::Cell;
+use support::run_server;
+
+/// 客户端连接后不读取任何数据, stall为true时发送一次请求, 服务端回复大量数据直到写入停滞
+fn run_timeout_server(addr: &'static str, read_timeout: u64, write_timeout: u64, life_timeout: u64, stall: bool) -> Option<ErrorKind> {
+    let kind = Rc::new(Cell::new(None));
+    let kind_clone = kind.clone();
+    let (tx, rx) = mpsc::channel();
+    let reason = run_server(addr, move || {
+        let (kind, tx) = (kind_clone.clone(), tx.clone());
+        EventEntry::new_event_fn(
+            0,
+            EventFlags::FLAG_READ | EventFlags::FLAG_PERSIST,
+            Some(Box::new(|ev, buffer| {
+                // 对端不读取, 填满内核缓冲后写入停滞
+                buffer.read.drain_all();
+                ev.send_socket(&buffer.as_raw_socket(), &vec![0; 64 * 1024 * 1024][..]).unwrap();
+                RetValue::OK
+            })),
+            None,
+            Some(Box::new(move |_, buffer| {
+                kind.set(buffer.error.as_ref().err().map(|e| e.kind()));
+                let _ = tx.send(());
+            })),
+        ).with_timeouts(read_timeout, write_timeout, life_timeout)
+    }, move |mut stream| {
+        if stall {
+            // 服务端结束前保持连接
+            stream.write_all(b"request").unwrap();
+            rx.recv().unwrap();
+            return;
+        }
+        let mut data = Vec::new();
+        stream.read_to_end(&mut data).unwrap();
+        assert!(data.is_empty());
+    });
+
+    assert_eq!(reason, Some(EndReason::Timeout));
+    kind.get()
+}
+
+#[test]
+fn test_read_idle_timeout() {
+    let reason = run_timeout_server("127.0.0.1:10011", 50_000, 0, 0, false);
+    assert_eq!(reason, Some(ErrorKind::TimedOut));
+}
+
+#[test]
+fn test_life_timeout() {
+    let reason = run_timeout_server("127.0.0.1:10012", 0, 0, 50_000, false);
+    assert_eq!(reason, Some(ErrorKind::TimedOut));
+}
+
+#[test]
+fn test_write_stall_timeout() {
+    let reason = run_timeout_server("127.0.0.1:10029", 0, 50_000, 0, true);
+    assert_eq!(reason, Some(ErrorKind::TimedOut));
+}
@@ -2,12 +2,12 @@ use td_revent::*;
 use std::fmt;
 
 static mut S_COUNT: u32 = 0;
-static mut S_DEL_TIMER: u32 = 0;
+static mut S_DEL_TIMER: TimerId = TimerId(0);
 
 //timer return no success(0) will no be repeat
 fn time_callback(
     ev: &mut EventLoop,
-    timer: u32,
+    timer: TimerId,
     data: Option<&mut CellAny>,
 ) -> (RetValue, u64) {
     if data.is_none() {
@@ -27,7 +27,7 @@ fn time_callback(
             return (RetValue::OK, 0);
         }
         println!("callback {:?}", obj);
-        return (RetValue::CONTINUE, 10);
+        return (RetValue::CONTINUE, 10_000);
     }
 
     if unsafe { S_DEL_TIMER == timer } {
@@ -61,16 +61,184 @@ pub fn test_timer() {
     let p = Point { x: 10, y: 20 };
 
     event_loop.add_timer(EventEntry::new_timer(
-        100,
+        100_000,
         false,
         Some(time_callback),
         Some(Box::new(p)),
-    ));
+    )).unwrap();
     unsafe {
         S_DEL_TIMER =
-            event_loop.add_timer(EventEntry::new_timer(150, true, Some(time_callback), None));
+            event_loop.add_timer(EventEntry::new_timer(150_000, true, Some(time_callback), None)).unwrap();
     }
-    event_loop.add_timer(EventEntry::new_timer(200, true, Some(time_callback), None));
+    event_loop.add_timer(EventEntry::new_timer(200_000, true, Some(time_callback), None)).unwrap();
     event_loop.run().unwrap();
     assert!(unsafe { S_COUNT } == 5);
 }
+
+#[test]
+pub fn test_timer_wheel() {
+    let mut timer = Timer::with_backend(u32::max_value() / 2, TimerBackend::Wheel { tick_us: 1000 });
+    let base = now_monotonic();
+    // 同一格, 第0层, 第1层, 第3层及超出时间轮范围的定时器
+    let ticks = [base + 10, base + 500, base + 70_000, base + 5_000_000_000, base + 20_000_000_000];
+    let mut ids = vec![];
+    for tick in ticks.iter() {
+        ids.push(timer.add_first_timer(EventEntry::new_timer_at_monotonic(*tick, None, None)).unwrap());
+    }
+    assert!(timer.del_timer(ids[1]).is_some());
+    assert!(timer.del_timer(ids[1]).is_none());
+
+    for &i in [0, 2, 3, 4].iter() {
+        assert!(timer.tick_time(ticks[i] - 1).is_none());
+        assert!(timer.tick_first().unwrap() <= ticks[i]);
+        let entry = timer.tick_time(ticks[i]).unwrap();
+        assert!(entry.time_id == ids[i]);
+    }
+    assert!(timer.tick_first().is_none());
+}
+
+#[test]
+pub fn test_timer_wheel_burst() {
+    // 同一格内大量定时器同时到期, 删除一半后其余的按到期时间依次取出
+    let mut timer = Timer::with_backend(u32::max_value() / 2, TimerBackend::Wheel { tick_us: 1000 });
+    let base = now_monotonic();
+    let mut ids = vec![];
+    for i in 0..20_000u64 {
+        let tick = base + 100 + (i * 7919) % 500;
+        ids.push((tick, timer.add_first_timer(EventEntry::new_timer_at_monotonic(tick, None, None)).unwrap()));
+    }
+    let now = base + 1_000;
+    assert!(timer.tick_first().unwrap() <= now);
+    // 先取出一个以展开到期的槽位, 之后删除的定时器均位于到期队列中
+    let first = timer.tick_time(now).unwrap();
+    let deleted = ids.iter()
+        .step_by(2)
+        .map(|&(_, id)| id)
+        .filter(|&id| id != first.time_id)
+        .collect::<::std::collections::HashSet<_>>();
+    for id in deleted.iter() {
+        assert!(timer.del_timer(*id).is_some());
+    }
+    let mut last = first.tick_us;
+    let mut count = 1;
+    while let Some(entry) = timer.tick_time(now) {
+        assert!(entry.tick_us >= last);
+        assert!(!deleted.contains(&entry.time_id));
+        last = entry.tick_us;
+        count += 1;
+    }
+    assert_eq!(count, ids.len() - deleted.len());
+    assert!(timer.tick_first().is_none());
+}
+
+#[test]
+pub fn test_timer_wheel_loop() {
+    let mut config = EventLoopConfig::default();
+    config.timer_backend = TimerBackend::Wheel { tick_us: 1000 };
+    let mut event_loop = EventLoop::configured(config).unwrap();
+    let count = ::std::rc::Rc::new(::std::cell::Cell::new(0));
+    let count_clone = count.clone();
+    event_loop.add_new_timer_fn(5_000, true, Box::new(move |ev, _timer| {
+        count_clone.set(count_clone.get() + 1);
+        if count_clone.get() >= 3 {
+            ev.shutdown();
+            return (RetValue::OVER, 0);
+        }
+        (RetValue::OK, 0)
+    })).unwrap();
+    event_loop.run().unwrap();
+    assert!(count.get() == 3);
+}
+
+fn shutdown_callback(ev: &mut EventLoop, _timer: TimerId, _data: Option<&mut CellAny>) -> (RetValue, u64) {
+    ev.shutdown();
+    (RetValue::OK, 0)
+}
+
+#[test]
+pub fn test_timer_at_wall() {
+    // add_new_timer_at的时间为墙上时间, 内部换算为单调时钟
+    let mut event_loop = EventLoop::new().unwrap();
+    let start = now_monotonic();
+    let time_id = event_loop.add_new_timer_at(now_micro() + 20_000, Some(shutdown_callback), None).unwrap();
+    assert!(event_loop.timer_remaining(time_id).unwrap() <= 20_000);
+    event_loop.run().unwrap();
+    assert!(now_monotonic() - start >= 20_000);
+    assert!(!event_loop.has_timer(time_id));
+}
+
+#[test]
+pub fn test_timer_reset() {
+    let mut event_loop = EventLoop::new().unwrap();
+    let fired = ::std::rc::Rc::new(::std::cell::Cell::new(TimerId(0)));
+    let fired_clone = fired.clone();
+    let time_id = event_loop.add_new_timer_fn(10_000_000, false, Box::new(move |ev, timer| {
+        fired_clone.set(timer);
+        ev.shutdown();
+        (RetValue::OK, 0)
+    })).unwrap();
+    assert!(event_loop.has_timer(time_id));
+    assert!(event_loop.timer_remaining(time_id).unwrap() > 5_000_000);
+
+    assert!(event_loop.reschedule_timer_at(time_id, now_monotonic() + 5_000_000));
+    assert!(event_loop.timer_remaining(time_id).unwrap() <= 5_000_000);
+
+    assert!(event_loop.reset_timer(time_id, 10_000));
+    assert!(event_loop.timer_remaining(time_id).unwrap() <= 10_000);
+
+    event_loop.run().unwrap();
+    assert!(fired.get() == time_id);
+    assert!(!event_loop.has_timer(time_id));
+    assert!(!event_loop.reset_timer(time_id, 10_000));
+}
+
+fn run_rate_timer(missed: MissedTick) -> Vec<u64> {
+    let mut event_loop = EventLoop::new().unwrap();
+    let calls = ::std::rc::Rc::new(::std::cell::RefCell::new(vec![]));
+    let calls_clone = calls.clone();
+    let start = now_monotonic();
+    event_loop.add_new_timer_rate_fn(20_000, missed, Box::new(move |ev, _timer| {
+        let mut calls = calls_clone.borrow_mut();
+        calls.push(now_monotonic() - start);
+        if calls.len() == 1 {
+            ::std::thread::sleep(::std::time::Duration::from_millis(50));
+        }
+        if calls.len() >= 4 {
+            ev.shutdown();
+            return (RetValue::OVER, 0);
+        }
+        (RetValue::OK, 0)
+    })).unwrap();
+    event_loop.run().unwrap();
+    let calls = calls.borrow().clone();
+    calls
+}
+
+#[test]
+pub fn test_timer_fixed_rate() {
+    // 首次在20ms到期, 回调耗时50ms, 错过了40ms及60ms的到期时间
+    let calls = run_rate_timer(MissedTick::CatchUp);
+    assert!(calls[1] < 80_000 && calls[2] < 80_000);
+
+    let calls = run_rate_timer(MissedTick::Skip);
+    assert!(calls[1] >= 80_000 && calls[2] >= 100_000);
+
+    let calls = run_rate_timer(MissedTick::Coalesce);
+    assert!(calls[1] < 80_000 && calls[2] >= 80_000);
+}
+
+#[test]
+pub fn test_timer_stale_id() {
+    let mut timer = Timer::new(2);
+    let first = timer.add_first_timer(EventEntry::new_timer(1_000_000, false, None, None)).unwrap();
+    assert!(timer.del_timer(first).is_some());
+    let second = timer.add_first_timer(EventEntry::new_timer(1_000_000, false, None, None)).unwrap();
+    // 槽位回绕后被重新使用, 旧的句柄不能删除新的定时器
+    let third = timer.add_first_timer(EventEntry::new_timer(1_000_000, false, None, None)).unwrap();
+    assert!(third.slot() == first.slot() && third != first);
+    assert!(timer.del_timer(first).is_none());
+    assert!(timer.has_timer(third));
+    assert!(timer.add_timer(EventEntry::new_timer(0, false, None, None)).is_err());
+    assert!(timer.add_first_timer(EventEntry::new_timer(1_000_000, false, None, None)).is_err());
+    assert!(timer.del_timer(second).is_some());
+}
@@ -0,0 +1,71 @@
+extern crate td_revent;
+extern crate psocket;
+
+use td_revent::*;
+use std::rc::Rc;
+use std::cell::Cell;
+use std::io;
+use self::psocket::{UdpSocket, ToSocketAddrs};
+
+#[test]
+fn test_udp_echo() {
+    let mut event_loop = EventLoop::new().unwrap();
+
+    let server = UdpSocket::bind("127.0.0.1:10015").unwrap();
+    let client = UdpSocket::bind("127.0.0.1:10016").unwrap();
+    let client_fd = client.as_raw_socket();
+    let server_addr = "127.0.0.1:10015".to_socket_addrs().unwrap().next().unwrap();
+
+    event_loop.add_udp_socket_fn(server, Box::new(|ev, socket, buf, addr| {
+        let _ = ev.send_to(&socket, buf, &addr);
+        RetValue::OK
+    })).unwrap();
+
+    let count = Rc::new(Cell::new(0));
+    let count_clone = count.clone();
+    event_loop.add_udp_socket_fn(client, Box::new(move |ev, socket, buf, addr| {
+        assert_eq!(buf, b"ping");
+        count_clone.set(count_clone.get() + 1);
+        if count_clone.get() >= 3 {
+            ev.shutdown();
+            return RetValue::OVER;
+        }
+        let _ = ev.send_to(&socket, buf, &addr);
+        RetValue::OK
+    })).unwrap();
+
+    event_loop.send_to(&client_fd, b"ping", &server_addr).unwrap();
+    event_loop.run().unwrap();
+    assert_eq!(count.get(), 3);
+}
+
+#[test]
+fn test_udp_error() {
+    let mut event_loop = EventLoop::new().unwrap();
+
+    // 连接到没有监听的端口, 发送后会收到ICMP端口不可达
+    let client = UdpSocket::bind("127.0.0.1:10031").unwrap();
+    client.connect("127.0.0.1:10032").unwrap();
+    let client_fd = client.as_raw_socket();
+    let server_addr = "127.0.0.1:10032".to_socket_addrs().unwrap().next().unwrap();
+
+    let refused = Rc::new(Cell::new(false));
+    let refused_clone = refused.clone();
+    let entry = EventEntry::new_udp_fn(client_fd, Box::new(|_, _, _, _| RetValue::OK))
+        .with_datagram_error_fn(Box::new(move |ev, _, err| {
+            assert_eq!(err.kind(), io::ErrorKind::ConnectionRefused);
+            refused_clone.set(true);
+            ev.shutdown();
+            RetValue::OVER
+        }));
+    event_loop.register_udp_socket(client, entry).unwrap();
+
+    event_loop.add_new_timer_fn(1_000_000, false, Box::new(|ev, _| {
+        ev.shutdown();
+        (RetValue::OVER, 0)
+    })).unwrap();
+
+    event_loop.send_to(&client_fd, b"ping", &server_addr).unwrap();
+    event_loop.run().unwrap();
+    assert!(refused.get());
+}
@@ -0,0 +1,103 @@
+extern crate td_revent;
+extern crate libc;
+
+use td_revent::*;
+use std::rc::Rc;
+use std::cell::Cell;
+use std::os::linux::net::SocketAddrExt;
+use std::os::unix::io::AsRawFd;
+use std::os::unix::net::{UnixListener, UnixDatagram, SocketAddr};
+
+#[test]
+fn test_unix_stream_echo() {
+    let mut event_loop = EventLoop::new().unwrap();
+
+    let addr = SocketAddr::from_abstract_name(b"td_revent_test_stream").unwrap();
+    let listener = UnixListener::bind_addr(&addr).unwrap();
+    event_loop.add_unix_listener_fn(listener, Some(Box::new(|ev, stream| {
+        let _ = ev.add_new_event_fn(
+            stream.unwrap(),
+            EventFlags::FLAG_READ | EventFlags::FLAG_PERSIST,
+            Some(Box::new(|ev, buffer| {
+                let data = buffer.read.drain_all_collect();
+                let _ = ev.send_socket(&buffer.as_raw_socket(), &data[..]);
+                RetValue::OK
+            })),
+            None,
+            None,
+        );
+        RetValue::OK
+    })), None).unwrap();
+
+    let count = Rc::new(Cell::new(0));
+    let count_clone = count.clone();
+    let socket = event_loop.connect_unix_fn(
+        &addr,
+        Some(Box::new(move |ev, buffer| {
+            let data = buffer.read.drain_all_collect();
+            assert_eq!(data, b"hello");
+            count_clone.set(count_clone.get() + 1);
+            if count_clone.get() >= 3 {
+                return RetValue::OVER;
+            }
+            let _ = ev.send_socket(&buffer.as_raw_socket(), &data[..]);
+            RetValue::OK
+        })),
+        Some(Box::new(|ev, _buffer| ev.shutdown())),
+    ).unwrap();
+    event_loop.send_socket(&socket, b"hello").unwrap();
+
+    event_loop.run().unwrap();
+    assert_eq!(count.get(), 3);
+}
+
+#[test]
+fn test_unix_datagram() {
+    let mut event_loop = EventLoop::new().unwrap();
+
+    let server_addr = SocketAddr::from_abstract_name(b"td_revent_test_dgram_server").unwrap();
+    let client_addr = SocketAddr::from_abstract_name(b"td_revent_test_dgram_client").unwrap();
+    let server = UnixDatagram::bind_addr(&server_addr).unwrap();
+    let client = UnixDatagram::bind_addr(&client_addr).unwrap();
+    let client_fd = client.as_raw_fd();
+
+    event_loop.add_unix_datagram_fn(server, Box::new(|ev, socket, buf, addr| {
+        let _ = ev.send_to_unix(&socket, buf, &addr);
+        RetValue::OK
+    })).unwrap();
+
+    let count = Rc::new(Cell::new(0));
+    let count_clone = count.clone();
+    event_loop.add_unix_datagram_fn(client, Box::new(move |ev, socket, buf, addr| {
+        assert_eq!(buf, b"ping");
+        count_clone.set(count_clone.get() + 1);
+        if count_clone.get() >= 3 {
+            ev.shutdown();
+            return RetValue::OVER;
+        }
+        let _ = ev.send_to_unix(&socket, buf, &addr);
+        RetValue::OK
+    })).unwrap();
+
+    event_loop.send_to_unix(&client_fd, b"ping", &server_addr).unwrap();
+    event_loop.run().unwrap();
+    assert_eq!(count.get(), 3);
+}
+
+#[test]
+fn test_unix_connect_backlog_full() {
+    let mut event_loop = EventLoop::new().unwrap();
+
+    // 不接受连接, 连接队列填满后connect_unix不阻塞, 直接返回WouldBlock
+    let addr = SocketAddr::from_abstract_name(b"td_revent_test_backlog").unwrap();
+    let listener = UnixListener::bind_addr(&addr).unwrap();
+    assert_eq!(unsafe { libc::listen(listener.as_raw_fd(), 1) }, 0);
+    let mut err = None;
+    for _ in 0..64 {
+        if let Err(e) = event_loop.connect_unix_fn(&addr, None, None) {
+            err = Some(e.kind());
+            break;
+        }
+    }
+    assert_eq!(err, Some(::std::io::ErrorKind::WouldBlock));
+}
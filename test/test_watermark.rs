@@ -0,0 +1,67 @@
+extern crate td_revent;
+
+use td_revent::*;
+use std::io::prelude::*;
+use std::io::ErrorKind;
+use std::rc::Rc;
+use std::cell::Cell;
+use support::run_server;
+
+const CHUNK_LEN: usize = 16 * 1024;
+const CHUNK_COUNT: usize = 256;
+
+/// 发送数据块直到达到高水位, 返回是否已全部发送
+fn produce(ev: &mut EventLoop, buffer: &EventBuffer, sent: &Cell<usize>, blocked: &Cell<usize>) -> bool {
+    while sent.get() < CHUNK_COUNT {
+        match ev.send_socket(&buffer.as_raw_socket(), &[sent.get() as u8; CHUNK_LEN]) {
+            Ok(_) => sent.set(sent.get() + 1),
+            Err(ref e) if e.kind() == ErrorKind::WouldBlock => {
+                blocked.set(blocked.get() + 1);
+                return false;
+            }
+            Err(e) => panic!("send error {:?}", e),
+        }
+        assert!(buffer.write.len() < 64 * 1024 + CHUNK_LEN);
+    }
+    true
+}
+
+#[test]
+fn test_write_watermark() {
+    let sent = Rc::new(Cell::new(0));
+    let blocked = Rc::new(Cell::new(0));
+    let writable = Rc::new(Cell::new(0));
+    let (sent_clone, blocked_clone, writable_clone) = (sent.clone(), blocked.clone(), writable.clone());
+    let reason = run_server("127.0.0.1:10025", move || {
+        let (sent, blocked) = (sent_clone.clone(), blocked_clone.clone());
+        let (sent_w, blocked_w, writable) = (sent_clone.clone(), blocked_clone.clone(), writable_clone.clone());
+        EventEntry::new_event_fn(
+            0,
+            EventFlags::FLAG_READ | EventFlags::FLAG_PERSIST,
+            Some(Box::new(move |ev, buffer| {
+                buffer.read.drain_all();
+                if produce(ev, buffer, &sent, &blocked) { RetValue::FLUSH } else { RetValue::OK }
+            })),
+            None,
+            None,
+        ).with_write_watermarks(64 * 1024, 16 * 1024)
+        .with_writable_fn(Box::new(move |ev, buffer| {
+            assert!(buffer.write.len() <= 16 * 1024);
+            writable.set(writable.get() + 1);
+            if produce(ev, buffer, &sent_w, &blocked_w) { RetValue::FLUSH } else { RetValue::OK }
+        }))
+    }, |mut stream| {
+        stream.write_all(b"request").unwrap();
+        let mut data = Vec::new();
+        stream.read_to_end(&mut data).unwrap();
+        assert_eq!(data.len(), CHUNK_LEN * CHUNK_COUNT);
+        for (i, chunk) in data.chunks(CHUNK_LEN).enumerate() {
+            assert!(chunk.iter().all(|&b| b == i as u8));
+        }
+    });
+
+    assert_eq!(reason, Some(EndReason::Flushed));
+    assert_eq!(sent.get(), CHUNK_COUNT);
+    assert!(blocked.get() > 0);
+    assert!(writable.get() >= blocked.get());
+}